@@ -0,0 +1,536 @@
+// Cellular automata, either on a fixed Grid or on an unbounded set of live
+// cells, stepped by rules that see a cell and its neighbours.
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    hash::Hash,
+    marker::PhantomData,
+};
+
+use enum_iterator::Sequence;
+use num_traits::{PrimInt, Signed};
+
+use crate::{
+    dir::Dir,
+    grud::{Grid, GridPos},
+};
+
+// The cells around a position, by direction. Off the grid they are None.
+#[derive(Debug, Clone)]
+pub struct Neighbours<Item, D> {
+    values: Vec<Option<Item>>,
+    phantom: PhantomData<D>,
+}
+
+impl<Item: Copy, D: Dir<DirType = D> + Sequence> Neighbours<Item, D> {
+    fn new(values: Vec<Option<Item>>) -> Neighbours<Item, D> {
+        Neighbours {
+            values,
+            phantom: PhantomData,
+        }
+    }
+
+    pub fn get(&self, d: D) -> Option<Item> {
+        self.values[d.to_i()]
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (D, Item)> + '_ {
+        D::cw().filter_map(|d| {
+            let v = self.values[d.to_i()]?;
+            Some((d, v))
+        })
+    }
+
+    pub fn count(&self, pred: impl Fn(Item) -> bool) -> usize {
+        self.values.iter().flatten().filter(|&&v| pred(v)).count()
+    }
+}
+
+// Where a sequence of states starts repeating: the state after start +
+// period steps is the same as after start.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Cycle {
+    pub start: usize,
+    pub period: usize,
+}
+
+impl Cycle {
+    pub fn new(start: usize, period: usize) -> Cycle {
+        Cycle { start, period }
+    }
+
+    // An earlier step with the same state as step n.
+    pub fn equivalent(&self, n: usize) -> usize {
+        if n < self.start {
+            n
+        } else {
+            self.start + (n - self.start) % self.period
+        }
+    }
+
+    // The value at step n of a series which grows by the same amount every
+    // period, like the height of a tower. The values must reach at least one
+    // period past the start.
+    pub fn extrapolate<T: PrimInt + Signed>(&self, values: &[T], n: usize) -> Option<T> {
+        if let Some(&v) = values.get(n) {
+            return Some(v);
+        }
+        let delta = *values.get(self.start + self.period)? - values[self.start];
+        let periods = T::from((n - self.start) / self.period)?;
+        Some(values[self.equivalent(n)] + delta * periods)
+    }
+}
+
+// Step a state until a key of it repeats, for up to max_steps.
+pub fn find_cycle<S, K: Hash + Eq>(
+    state: &mut S,
+    step: impl FnMut(&mut S),
+    key: impl Fn(&S) -> K,
+    max_steps: usize,
+) -> Option<Cycle> {
+    find_cycle_recording(state, step, key, |_| (), max_steps).map(|(cycle, _)| cycle)
+}
+
+// As find_cycle, also recording a value of the state after every step,
+// from the initial state up to the repeat, to extrapolate with.
+pub fn find_cycle_recording<S, K: Hash + Eq, V>(
+    state: &mut S,
+    mut step: impl FnMut(&mut S),
+    key: impl Fn(&S) -> K,
+    value: impl Fn(&S) -> V,
+    max_steps: usize,
+) -> Option<(Cycle, Vec<V>)> {
+    let mut seen = HashMap::new();
+    let mut values = Vec::new();
+    for n in 0..=max_steps {
+        values.push(value(state));
+        if let Some(start) = seen.insert(key(state), n) {
+            return Some((Cycle::new(start, n - start), values));
+        }
+        if n < max_steps {
+            step(state);
+        }
+    }
+    None
+}
+
+// A double buffered grid automaton.
+#[derive(Debug, Clone)]
+pub struct GridAutomaton<Item, D>
+where
+    Item: Copy + Eq,
+    D: Dir + Sequence,
+{
+    grid: Grid<Item, D>,
+    next: Grid<Item, D>,
+    generation: usize,
+}
+
+impl<Item, D> GridAutomaton<Item, D>
+where
+    Item: Copy + Eq,
+    D: Dir<DirType = D> + Sequence + Clone,
+{
+    pub fn new(grid: Grid<Item, D>) -> GridAutomaton<Item, D> {
+        GridAutomaton {
+            next: grid.clone(),
+            grid,
+            generation: 0,
+        }
+    }
+
+    pub fn grid(&self) -> &Grid<Item, D> {
+        &self.grid
+    }
+
+    pub fn into_grid(self) -> Grid<Item, D> {
+        self.grid
+    }
+
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    pub fn neighbours(&self, pos: GridPos) -> Neighbours<Item, D> {
+        Neighbours::new(
+            D::cw()
+                .map(|d| self.grid.at(&(pos + d.to_vec2d())))
+                .collect(),
+        )
+    }
+
+    // Replace every cell at once by what the rule gives for it, returning the
+    // number of cells that changed.
+    pub fn step(&mut self, rule: impl Fn(GridPos, Item, &Neighbours<Item, D>) -> Item) -> usize {
+        let mut changed = 0;
+        for (pos, item) in self.grid.iter_pair() {
+            let new = rule(pos, item, &self.neighbours(pos));
+            changed += (new != item) as usize;
+            self.next.set(&pos, new);
+        }
+        std::mem::swap(&mut self.grid, &mut self.next);
+        self.generation += 1;
+        changed
+    }
+
+    // One step of cascading updates: every cell is updated by `start`, then
+    // each cell for which `fires` holds fires once, applying `spread` to each
+    // of its neighbours, which may fire in turn. Finally the cells that fired
+    // are updated by `after`. Returns the cells that fired, in order.
+    pub fn cascade(
+        &mut self,
+        start: impl Fn(Item) -> Item,
+        fires: impl Fn(Item) -> bool,
+        spread: impl Fn(Item) -> Item,
+        after: impl Fn(Item) -> Item,
+    ) -> Vec<GridPos> {
+        let positions = self.grid.iter_pos().collect::<Vec<_>>();
+        for pos in &positions {
+            let item = self.grid.at(pos).unwrap();
+            self.grid.set(pos, start(item));
+        }
+        let mut fired = Grid::<bool, D>::new(false, self.grid.width(), self.grid.height());
+        let mut pending = positions
+            .into_iter()
+            .filter(|p| fires(self.grid.at(p).unwrap()))
+            .collect::<Vec<_>>();
+        let mut order = Vec::new();
+        while let Some(pos) = pending.pop() {
+            if fired.set(&pos, true) == Some(true) {
+                continue;
+            }
+            order.push(pos);
+            for d in D::cw() {
+                let n = pos + d.to_vec2d();
+                let Some(item) = self.grid.at(&n) else {
+                    continue;
+                };
+                let item = spread(item);
+                self.grid.set(&n, item);
+                if fires(item) && fired.at(&n) == Some(false) {
+                    pending.push(n);
+                }
+            }
+        }
+        for pos in &order {
+            let item = self.grid.at(pos).unwrap();
+            self.grid.set(pos, after(item));
+        }
+        self.generation += 1;
+        order
+    }
+
+    // Step until a generation changes nothing, returning that generation's
+    // number (counting from 1).
+    pub fn run_until_stable(
+        &mut self,
+        rule: impl Fn(GridPos, Item, &Neighbours<Item, D>) -> Item,
+        max_generations: usize,
+    ) -> Option<usize> {
+        (0..max_generations).find_map(|_| (self.step(&rule) == 0).then_some(self.generation))
+    }
+
+    pub fn find_cycle(
+        &mut self,
+        rule: impl Fn(GridPos, Item, &Neighbours<Item, D>) -> Item,
+        max_generations: usize,
+    ) -> Option<Cycle>
+    where
+        Item: Hash,
+    {
+        find_cycle(
+            self,
+            |a| {
+                a.step(&rule);
+            },
+            |a| a.grid.iter().copied().collect::<Vec<_>>(),
+            max_generations,
+        )
+    }
+}
+
+// An unbounded automaton of live cells.
+#[derive(Debug, Clone)]
+pub struct SparseAutomaton<D> {
+    cells: HashSet<GridPos>,
+    generation: usize,
+    phantom: PhantomData<D>,
+}
+
+impl<D: Dir<DirType = D> + Sequence> SparseAutomaton<D> {
+    pub fn new(cells: impl IntoIterator<Item = GridPos>) -> SparseAutomaton<D> {
+        SparseAutomaton {
+            cells: cells.into_iter().collect(),
+            generation: 0,
+            phantom: PhantomData,
+        }
+    }
+
+    // The positions of the given character in a picture.
+    pub fn parse(input: &str, live: char) -> SparseAutomaton<D> {
+        SparseAutomaton::new(input.lines().enumerate().flat_map(|(y, line)| {
+            line.chars()
+                .enumerate()
+                .filter(move |&(_, c)| c == live)
+                .map(move |(x, _)| GridPos::new(x as i64, y as i64))
+        }))
+    }
+
+    pub fn cells(&self) -> &HashSet<GridPos> {
+        &self.cells
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn contains(&self, pos: &GridPos) -> bool {
+        self.cells.contains(pos)
+    }
+
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    // The smallest and largest corners of the live cells.
+    pub fn bounds(&self) -> Option<(GridPos, GridPos)> {
+        let mut cells = self.cells.iter();
+        let first = *cells.next()?;
+        Some(cells.fold((first, first), |(min, max), p| {
+            (
+                GridPos::new(min.x.min(p.x), min.y.min(p.y)),
+                GridPos::new(max.x.max(p.x), max.y.max(p.y)),
+            )
+        }))
+    }
+
+    // Dead cells within the bounds
+    pub fn empty_in_bounds(&self) -> usize {
+        self.bounds().map_or(0, |(min, max)| {
+            ((max.x - min.x + 1) * (max.y - min.y + 1)) as usize - self.cells.len()
+        })
+    }
+
+    pub fn neighbours(&self, pos: GridPos) -> Neighbours<bool, D> {
+        Neighbours::new(
+            D::cw()
+                .map(|d| Some(self.contains(&(pos + d.to_vec2d()))))
+                .collect(),
+        )
+    }
+
+    // Life-like step: every live cell and every cell next to one becomes live
+    // or dead by the rule. Returns the number of cells that changed.
+    pub fn step(&mut self, rule: impl Fn(GridPos, bool, &Neighbours<bool, D>) -> bool) -> usize {
+        let candidates = self
+            .cells
+            .iter()
+            .flat_map(|&p| D::cw().map(move |d| p + d.to_vec2d()).chain([p]))
+            .collect::<HashSet<_>>();
+        let next = candidates
+            .into_iter()
+            .filter(|&p| rule(p, self.contains(&p), &self.neighbours(p)))
+            .collect::<HashSet<_>>();
+        let changed = next.symmetric_difference(&self.cells).count();
+        self.cells = next;
+        self.generation += 1;
+        changed
+    }
+
+    // Move cells in two phases: each live cell may propose a new position,
+    // given the generation, then every cell whose proposal no other cell
+    // shares moves there. Returns the number of cells that moved.
+    pub fn step_moves(
+        &mut self,
+        propose: impl Fn(GridPos, &Neighbours<bool, D>, usize) -> Option<GridPos>,
+    ) -> usize {
+        let proposals = self
+            .cells
+            .iter()
+            .filter_map(|&p| Some((p, propose(p, &self.neighbours(p), self.generation)?)))
+            .collect::<Vec<_>>();
+        let mut wanted = HashMap::new();
+        for (_, to) in &proposals {
+            *wanted.entry(*to).or_insert(0) += 1;
+        }
+        let moves = proposals
+            .into_iter()
+            .filter(|(_, to)| wanted[to] == 1)
+            .collect::<Vec<_>>();
+        for (from, _) in &moves {
+            self.cells.remove(from);
+        }
+        self.cells.extend(moves.iter().map(|&(_, to)| to));
+        self.generation += 1;
+        moves.len()
+    }
+
+    // Move until a generation where nothing moves, returning its number
+    // (counting from 1).
+    pub fn run_moves_until_stable(
+        &mut self,
+        propose: impl Fn(GridPos, &Neighbours<bool, D>, usize) -> Option<GridPos>,
+        max_generations: usize,
+    ) -> Option<usize> {
+        (0..max_generations)
+            .find_map(|_| (self.step_moves(&propose) == 0).then_some(self.generation))
+    }
+
+    pub fn find_cycle(
+        &mut self,
+        rule: impl Fn(GridPos, bool, &Neighbours<bool, D>) -> bool,
+        max_generations: usize,
+    ) -> Option<Cycle> {
+        find_cycle(
+            self,
+            |a| {
+                a.step(&rule);
+            },
+            |a| {
+                let mut cells = a.cells.iter().copied().collect::<Vec<_>>();
+                cells.sort();
+                cells
+            },
+            max_generations,
+        )
+    }
+}
+
+impl<D> Display for SparseAutomaton<D>
+where
+    D: Dir<DirType = D> + Sequence,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Some((min, max)) = self.bounds() else {
+            return Ok(());
+        };
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                let c = if self.contains(&GridPos::new(x, y)) {
+                    '#'
+                } else {
+                    '.'
+                };
+                write!(f, "{c}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dir::{Dir4, Dir8};
+
+    #[test]
+    fn test_flashes() {
+        let input = "5483143223
+2745854711
+5264556173
+6141336146
+6357385478
+4167524645
+2176841721
+6882881134
+4846848554
+5283751526";
+        let grid = Grid::<u8, Dir8>::parse_items(input, |c| c as u8 - b'0', |_, _, _| true);
+        let mut octopuses = GridAutomaton::new(grid);
+        let step = |o: &mut GridAutomaton<u8, Dir8>| {
+            o.cascade(|e| e + 1, |e| e > 9, |e| e + 1, |_| 0).len()
+        };
+        let flashes = (0..100).map(|_| step(&mut octopuses)).sum::<usize>();
+        assert_eq!(flashes, 1656);
+        let all = (101..).find(|_| step(&mut octopuses) == 100);
+        assert_eq!(all, Some(195));
+        assert_eq!(octopuses.generation(), 195);
+    }
+
+    #[test]
+    fn test_elves() {
+        use Dir8::*;
+        let input = "....#..
+..###.#
+#...#.#
+.#...##
+#.###..
+##.#.##
+.#..#..";
+        let checks = [[N, NE, NW], [S, SE, SW], [W, NW, SW], [E, NE, SE]];
+        let propose = |pos: GridPos, n: &Neighbours<bool, Dir8>, generation: usize| {
+            if n.count(|live| live) == 0 {
+                return None;
+            }
+            (0..4)
+                .map(|i| checks[(generation + i) % 4])
+                .find(|dirs| dirs.iter().all(|&d| n.get(d) == Some(false)))
+                .map(|dirs| pos + dirs[0].to_vec2d())
+        };
+        let mut elves = SparseAutomaton::<Dir8>::parse(input, '#');
+        for _ in 0..10 {
+            elves.step_moves(propose);
+        }
+        assert_eq!(elves.empty_in_bounds(), 110);
+        assert_eq!(elves.run_moves_until_stable(propose, 100), Some(20));
+    }
+
+    #[test]
+    fn test_life() {
+        let life = |_, live, n: &Neighbours<bool, Dir8>| {
+            matches!((live, n.count(|l| l)), (true, 2) | (_, 3))
+        };
+        let mut blinker = SparseAutomaton::<Dir8>::parse(".#.\n.#.\n.#.", '#');
+        assert_eq!(blinker.step(life), 4);
+        assert_eq!(blinker.to_string(), "###\n");
+        assert_eq!(
+            blinker.find_cycle(life, 10),
+            Some(Cycle {
+                start: 0,
+                period: 2
+            })
+        );
+
+        let grid = Grid::<char, Dir8>::parse("....\n.##.\n.##.\n....");
+        let mut block = GridAutomaton::new(grid);
+        let rule = |_, c, n: &Neighbours<char, Dir8>| {
+            let live = if c == '#' { 1 } else { 0 };
+            match (live, n.count(|c| c == '#')) {
+                (1, 2) | (_, 3) => '#',
+                _ => '.',
+            }
+        };
+        assert_eq!(block.run_until_stable(rule, 10), Some(1));
+
+        // Flood fill
+        let grid = Grid::<char, Dir4>::parse(".#..\n..#.\n###.\n....");
+        let spread = |_, c, n: &Neighbours<char, Dir4>| {
+            if n.iter().any(|(_, c)| c == '#') {
+                '#'
+            } else {
+                c
+            }
+        };
+        let mut fill = GridAutomaton::new(grid);
+        assert_eq!(fill.run_until_stable(spread, 10), Some(3));
+        assert_eq!(
+            fill.find_cycle(spread, 10),
+            Some(Cycle {
+                start: 0,
+                period: 1
+            })
+        );
+
+        let cycle = Cycle {
+            start: 3,
+            period: 4,
+        };
+        assert_eq!(cycle.equivalent(2), 2);
+        assert_eq!(cycle.equivalent(1_000_000_000), 3 + (1_000_000_000 - 3) % 4);
+    }
+}
@@ -0,0 +1,451 @@
+use std::rc::Rc;
+
+// How deeply a layout may recurse into itself before reading fails.
+pub const MAX_DEPTH: usize = 256;
+
+// How the bits of a multi-bit field make up its value: big endian means the
+// first bit read is the most significant.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Endian {
+    #[default]
+    Big,
+    Little,
+}
+
+// A piece of a declarative packet layout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Field {
+    // A fixed width unsigned value
+    Bits(usize),
+    // Groups of the given width, each led by a flag bit which is set on every
+    // group except the last
+    Chunked(usize),
+    // Fields one after another
+    Group(Vec<Field>),
+    // Within a group, the layout to read next chosen by the value of an
+    // earlier field of the group, by index, with a default for other values
+    Match(usize, Vec<(u64, Field)>, Box<Field>),
+    // A flag bit, then the first layout if it is clear or the second if set
+    Either(Box<Field>, Box<Field>),
+    // A length of the given width, then the field repeated to fill that many
+    // bits
+    LengthPrefixed(usize, Box<Field>),
+    // A count of the given width, then the field repeated that many times
+    CountPrefixed(usize, Box<Field>),
+    // The whole layout being read, for packets nested in packets
+    Recurse,
+}
+
+// What a Field reads as.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Number(u64),
+    // From a group
+    Group(Vec<Value>),
+    // From a length or count prefixed field
+    List(Vec<Value>),
+}
+
+impl Value {
+    pub fn number(&self) -> Option<u64> {
+        match self {
+            Value::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    // The values of a group or list
+    pub fn items(&self) -> &[Value] {
+        match self {
+            Value::Number(_) => &[],
+            Value::Group(items) | Value::List(items) => items,
+        }
+    }
+}
+
+// Reads fields of any width from a stream of bits. Sub-streams share the
+// underlying bits with the reader they were sliced from.
+#[derive(Debug, Clone)]
+pub struct BitReader {
+    bits: Rc<[bool]>,
+    start: usize,
+    end: usize,
+    pos: usize,
+    endian: Endian,
+}
+
+impl BitReader {
+    pub fn new(bits: Vec<bool>) -> BitReader {
+        let end = bits.len();
+        BitReader {
+            bits: bits.into(),
+            start: 0,
+            end,
+            pos: 0,
+            endian: Endian::Big,
+        }
+    }
+
+    // Each hex digit is four bits, most significant first.
+    pub fn from_hex(s: &str) -> Option<BitReader> {
+        let bits = s
+            .trim()
+            .chars()
+            .map(|c| c.to_digit(16))
+            .collect::<Option<Vec<_>>>()?
+            .into_iter()
+            .flat_map(|d| (0..4).rev().map(move |b| d & (1 << b) != 0))
+            .collect();
+        Some(BitReader::new(bits))
+    }
+
+    pub fn from_binary(s: &str) -> Option<BitReader> {
+        let bits = s
+            .trim()
+            .chars()
+            .map(|c| match c {
+                '0' => Some(false),
+                '1' => Some(true),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(BitReader::new(bits))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> BitReader {
+        let bits = bytes
+            .iter()
+            .flat_map(|&byte| (0..8).rev().map(move |b| byte & (1 << b) != 0))
+            .collect();
+        BitReader::new(bits)
+    }
+
+    pub fn with_endian(self, endian: Endian) -> BitReader {
+        BitReader { endian, ..self }
+    }
+
+    pub fn endian(&self) -> Endian {
+        self.endian
+    }
+
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Bits read so far
+    pub fn position(&self) -> usize {
+        self.pos - self.start
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.end - self.pos
+    }
+
+    pub fn seek(&mut self, position: usize) -> Option<()> {
+        (position <= self.len()).then(|| self.pos = self.start + position)
+    }
+
+    pub fn skip(&mut self, n: usize) -> Option<()> {
+        self.seek(self.position() + n)
+    }
+
+    pub fn read_bit(&mut self) -> Option<bool> {
+        let bit = self.peek_bit()?;
+        self.pos += 1;
+        Some(bit)
+    }
+
+    pub fn peek_bit(&self) -> Option<bool> {
+        (self.pos < self.end).then(|| self.bits[self.pos])
+    }
+
+    // None if there aren't n bits left, or they won't fit in a u64.
+    pub fn peek_bits(&self, n: usize) -> Option<u64> {
+        if n > 64 || n > self.remaining() {
+            return None;
+        }
+        let bits = &self.bits[self.pos..self.pos + n];
+        let value = match self.endian {
+            Endian::Big => bits.iter().fold(0, |v, &b| (v << 1) | b as u64),
+            Endian::Little => bits.iter().rev().fold(0, |v, &b| (v << 1) | b as u64),
+        };
+        Some(value)
+    }
+
+    pub fn read_bits(&mut self, n: usize) -> Option<u64> {
+        let value = self.peek_bits(n)?;
+        self.pos += n;
+        Some(value)
+    }
+
+    // The value made from chunks of `width` bits, each led by a continuation
+    // flag, with the first chunk the most significant. None if the value
+    // won't fit in a u64.
+    pub fn read_chunked(&mut self, width: usize) -> Option<u64> {
+        let start = self.pos;
+        let value = self.read_chunks(width);
+        if value.is_none() {
+            self.pos = start;
+        }
+        value
+    }
+
+    fn read_chunks(&mut self, width: usize) -> Option<u64> {
+        let (mut value, mut bits) = (0u64, 0);
+        loop {
+            let more = self.read_bit()?;
+            let chunk = self.read_bits(width)?;
+            bits += width;
+            if bits > 64 {
+                return None;
+            }
+            value = value.checked_shl(width as u32).unwrap_or(0) | chunk;
+            if !more {
+                return Some(value);
+            }
+        }
+    }
+
+    // Consume the next n bits as a reader of their own.
+    pub fn sub_reader(&mut self, n: usize) -> Option<BitReader> {
+        if n > self.remaining() {
+            return None;
+        }
+        let sub = BitReader {
+            bits: self.bits.clone(),
+            start: self.pos,
+            end: self.pos + n,
+            pos: self.pos,
+            endian: self.endian,
+        };
+        self.pos += n;
+        Some(sub)
+    }
+
+    // Read a field of a layout, leaving the position unchanged on failure.
+    pub fn read_field(&mut self, field: &Field) -> Option<Value> {
+        let start = self.pos;
+        let value = self.read_within(field, field, &[], 0);
+        if value.is_none() {
+            self.pos = start;
+        }
+        value
+    }
+
+    pub fn read_fields(&mut self, fields: &[Field]) -> Option<Vec<Value>> {
+        let group = Field::Group(fields.to_vec());
+        match self.read_field(&group)? {
+            Value::Group(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    // Read a field of the root layout, with the values read so far in the
+    // group it's in, and how many times the root has been recursed into.
+    fn read_within(
+        &mut self,
+        field: &Field,
+        root: &Field,
+        group: &[Value],
+        depth: usize,
+    ) -> Option<Value> {
+        let value = match field {
+            Field::Bits(n) => Value::Number(self.read_bits(*n)?),
+            Field::Chunked(width) => Value::Number(self.read_chunked(*width)?),
+            Field::Group(fields) => {
+                let mut values = Vec::new();
+                for f in fields {
+                    let value = self.read_within(f, root, &values, depth)?;
+                    values.push(value);
+                }
+                Value::Group(values)
+            }
+            Field::Match(index, cases, default) => {
+                let on = group.get(*index)?.number()?;
+                let case = cases.iter().find(|(v, _)| *v == on);
+                self.read_within(case.map_or(default, |(_, f)| f), root, group, depth)?
+            }
+            Field::Either(clear, set) => {
+                let chosen = if self.read_bit()? { set } else { clear };
+                self.read_within(chosen, root, group, depth)?
+            }
+            Field::LengthPrefixed(width, item) => {
+                let len = usize::try_from(self.read_bits(*width)?).ok()?;
+                let mut sub = self.sub_reader(len)?;
+                let mut values = Vec::new();
+                while sub.remaining() > 0 {
+                    values.push(sub.read_item(item, root, depth)?);
+                }
+                Value::List(values)
+            }
+            Field::CountPrefixed(width, item) => {
+                let count = self.read_bits(*width)?;
+                let values = (0..count)
+                    .map(|_| self.read_item(item, root, depth))
+                    .collect::<Option<Vec<_>>>()?;
+                Value::List(values)
+            }
+            Field::Recurse if depth < MAX_DEPTH => self.read_within(root, root, &[], depth + 1)?,
+            Field::Recurse => return None,
+        };
+        Some(value)
+    }
+
+    // An item of a list, which must consume some bits so the list ends.
+    fn read_item(&mut self, item: &Field, root: &Field, depth: usize) -> Option<Value> {
+        let start = self.pos;
+        let value = self.read_within(item, root, &[], depth)?;
+        (self.pos > start).then_some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A BITS packet: version, type, then a literal for type 4 or else
+    // sub-packets, either filling a length in bits or by count.
+    fn bits_packet() -> Field {
+        let packets = Field::Either(
+            Box::new(Field::LengthPrefixed(15, Box::new(Field::Recurse))),
+            Box::new(Field::CountPrefixed(11, Box::new(Field::Recurse))),
+        );
+        Field::Group(vec![
+            Field::Bits(3),
+            Field::Bits(3),
+            Field::Match(1, vec![(4, Field::Chunked(4))], Box::new(packets)),
+        ])
+    }
+
+    fn version_sum(packet: &Value) -> u64 {
+        let [version, _, contents] = packet.items() else {
+            return 0;
+        };
+        let inner = contents.items().iter().map(version_sum).sum::<u64>();
+        version.number().unwrap() + inner
+    }
+
+    fn evaluate(packet: &Value) -> Option<u64> {
+        let [_, type_id, contents] = packet.items() else {
+            return None;
+        };
+        let args = contents
+            .items()
+            .iter()
+            .map(evaluate)
+            .collect::<Option<Vec<_>>>()?;
+        let value = match type_id.number()? {
+            0 => args.iter().sum(),
+            1 => args.iter().product(),
+            2 => *args.iter().min()?,
+            3 => *args.iter().max()?,
+            4 => contents.number()?,
+            5 => (args[0] > args[1]) as u64,
+            6 => (args[0] < args[1]) as u64,
+            _ => (args[0] == args[1]) as u64,
+        };
+        Some(value)
+    }
+
+    #[test]
+    fn test_bits_packets() {
+        let mut r = BitReader::from_hex("D2FE28").unwrap();
+        let header = r.read_fields(&[Field::Bits(3), Field::Bits(3)]);
+        assert_eq!(header, Some(vec![Value::Number(6), Value::Number(4)]));
+        assert_eq!(r.read_chunked(4), Some(2021));
+        assert_eq!(r.position(), 21);
+        assert_eq!(r.remaining(), 3);
+
+        let mut r = BitReader::from_hex("38006F45291200").unwrap();
+        assert_eq!(r.read_bits(7), Some(0b0011100));
+        assert_eq!(r.read_bits(15), Some(27));
+        let mut sub = r.sub_reader(27).unwrap();
+        assert_eq!(sub.len(), 27);
+        sub.skip(6).unwrap();
+        assert_eq!(sub.read_chunked(4), Some(10));
+        sub.skip(6).unwrap();
+        assert_eq!(sub.read_chunked(4), Some(20));
+        assert!(sub.peek_bit().is_none());
+        assert_eq!(r.position(), 49);
+
+        let read = |hex| {
+            let mut r = BitReader::from_hex(hex).unwrap();
+            r.read_field(&bits_packet()).unwrap()
+        };
+        let packet = read("38006F45291200");
+        let contents = &packet.items()[2];
+        assert_eq!(contents.items().len(), 2);
+        assert_eq!(contents.items()[1].items()[2], Value::Number(20));
+        for (hex, sum) in [
+            ("8A004A801A8002F478", 16),
+            ("620080001611562C8802118E34", 12),
+            ("C0015000016115A2E0802F182340", 23),
+            ("A0016C880162017C3686B18A3D4780", 31),
+        ] {
+            assert_eq!(version_sum(&read(hex)), sum);
+        }
+        assert_eq!(evaluate(&read("C200B40A82")), Some(3));
+        assert_eq!(evaluate(&read("9C0141080250320F1802104A08")), Some(1));
+    }
+
+    #[test]
+    fn test_bit_reader() {
+        let mut r = BitReader::from_binary("0011").unwrap();
+        assert_eq!(r.peek_bits(4), Some(3));
+        let mut little = r.clone().with_endian(Endian::Little);
+        assert_eq!(little.read_bits(4), Some(12));
+        assert_eq!(r.read_bits(5), None);
+        assert_eq!(r.read_fields(&[Field::Bits(2), Field::Bits(3)]), None);
+        assert_eq!(r.position(), 0);
+        assert!(BitReader::from_binary("012").is_none());
+
+        let mut r = BitReader::from_bytes(&[0xa5, 0xff]);
+        assert_eq!(r.read_bits(4), Some(0xa));
+        assert_eq!(r.read_bits(12), Some(0x5ff));
+        assert_eq!(r.remaining(), 0);
+        assert!(!r.is_empty());
+
+        // Widths from the input which won't fit are errors, not panics
+        let mut r = BitReader::from_bytes(&[0xff; 20]);
+        assert_eq!(r.peek_bits(65), None);
+        assert_eq!(r.read_bits(64), Some(u64::MAX));
+        let mut r = BitReader::from_binary(&format!("{}01111", "11111".repeat(16))).unwrap();
+        assert_eq!(r.read_chunked(4), None);
+        assert_eq!(r.position(), 0);
+        let mut r = BitReader::from_binary(&format!("{}01111", "11111".repeat(15))).unwrap();
+        assert_eq!(r.read_chunked(4), Some(u64::MAX));
+    }
+
+    #[test]
+    fn test_no_progress() {
+        let empty = Box::new(Field::Group(Vec::new()));
+        let mut r = BitReader::from_binary("1111111100").unwrap();
+        assert_eq!(r.read_field(&Field::LengthPrefixed(2, empty.clone())), None);
+        assert_eq!(
+            r.read_field(&Field::CountPrefixed(8, Box::new(Field::Bits(0)))),
+            None
+        );
+        assert_eq!(r.position(), 0);
+        // An empty list needs no progress
+        r.skip(8).unwrap();
+        let list = r.read_field(&Field::LengthPrefixed(2, empty)).unwrap();
+        assert_eq!(list, Value::List(Vec::new()));
+
+        // A layout which is only itself, or only itself after a bit
+        let mut r = BitReader::from_binary("0").unwrap();
+        assert_eq!(r.read_field(&Field::Recurse), None);
+        let nested = Field::Group(vec![Field::Either(
+            Box::new(Field::Group(Vec::new())),
+            Box::new(Field::Recurse),
+        )]);
+        let mut r = BitReader::from_binary(&"1".repeat(1000)).unwrap();
+        assert_eq!(r.read_field(&nested), None);
+        let mut r = BitReader::from_binary("1110").unwrap();
+        assert!(r.read_field(&nested).is_some());
+        assert_eq!(r.remaining(), 0);
+    }
+}
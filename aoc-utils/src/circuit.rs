@@ -0,0 +1,460 @@
+// Netlists of two input logic gates, written like "x00 AND y00 -> z00",
+// optionally preceded by a block of initial wire values like "x00: 1".
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt::Display,
+};
+
+use graphviz_rust::dot_generator::*;
+use graphviz_rust::dot_structures::*;
+
+pub type WireId = usize;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Op {
+    And,
+    Or,
+    Xor,
+}
+
+impl Op {
+    pub fn parse(s: &str) -> Option<Op> {
+        match s {
+            "AND" => Some(Op::And),
+            "OR" => Some(Op::Or),
+            "XOR" => Some(Op::Xor),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Op::And => "AND",
+            Op::Or => "OR",
+            Op::Xor => "XOR",
+        }
+    }
+
+    pub fn apply(&self, a: bool, b: bool) -> bool {
+        match self {
+            Op::And => a & b,
+            Op::Or => a | b,
+            Op::Xor => a ^ b,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Gate {
+    pub op: Op,
+    pub inputs: [WireId; 2],
+    pub output: WireId,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Circuit {
+    names: Vec<String>,
+    ids: HashMap<String, WireId>,
+    gates: Vec<Gate>,
+    initial: Vec<Option<bool>>,
+}
+
+impl Circuit {
+    pub fn new() -> Circuit {
+        Circuit::default()
+    }
+
+    // The id of a wire, adding it if it's new.
+    pub fn add_wire(&mut self, name: &str) -> WireId {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+        self.names.push(name.to_string());
+        self.initial.push(None);
+        self.ids.insert(name.to_string(), self.names.len() - 1);
+        self.names.len() - 1
+    }
+
+    pub fn add_gate(&mut self, a: &str, op: Op, b: &str, output: &str) -> &mut Self {
+        let inputs = [self.add_wire(a), self.add_wire(b)];
+        let output = self.add_wire(output);
+        self.gates.push(Gate { op, inputs, output });
+        self
+    }
+
+    pub fn parse(input: &str) -> Option<Circuit> {
+        let mut circuit = Circuit::new();
+        for line in input.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            if let Some((wire, value)) = line.split_once(": ") {
+                let id = circuit.add_wire(wire);
+                circuit.initial[id] = Some(value.trim().parse::<u8>().ok()? != 0);
+                continue;
+            }
+            let [a, op, b, "->", out] = line.split_whitespace().collect::<Vec<_>>()[..] else {
+                return None;
+            };
+            circuit.add_gate(a, Op::parse(op)?, b, out);
+        }
+        Some(circuit)
+    }
+
+    pub fn wire(&self, name: &str) -> Option<WireId> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, id: WireId) -> &str {
+        &self.names[id]
+    }
+
+    pub fn wires(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn gates(&self) -> &[Gate] {
+        &self.gates
+    }
+
+    pub fn set(&mut self, wire: WireId, value: Option<bool>) {
+        self.initial[wire] = value;
+    }
+
+    // Wires named prefix followed by a bit number, least significant first.
+    pub fn bus(&self, prefix: &str) -> Vec<WireId> {
+        let mut bus = self
+            .names
+            .iter()
+            .enumerate()
+            .filter_map(|(id, name)| {
+                let bit = name.strip_prefix(prefix)?.parse::<usize>().ok()?;
+                Some((bit, id))
+            })
+            .collect::<Vec<_>>();
+        bus.sort();
+        bus.into_iter().map(|(_, id)| id).collect()
+    }
+
+    // Set the initial values of a bus from the bits of a number.
+    pub fn set_number(&mut self, prefix: &str, value: u64) {
+        for (bit, id) in self.bus(prefix).into_iter().enumerate() {
+            self.initial[id] = Some(bit < 64 && value >> bit & 1 == 1);
+        }
+    }
+
+    pub fn number(&self, values: &[Option<bool>], prefix: &str) -> Option<u64> {
+        self.bus(prefix)
+            .iter()
+            .rev()
+            .try_fold(0, |n, &id| Some(n << 1 | values[id]? as u64))
+    }
+
+    // The gate driving each wire.
+    pub fn drivers(&self) -> Vec<Option<usize>> {
+        let mut drivers = vec![None; self.names.len()];
+        for (g, gate) in self.gates.iter().enumerate() {
+            drivers[gate.output] = Some(g);
+        }
+        drivers
+    }
+
+    // The gates reading each wire.
+    pub fn consumers(&self) -> Vec<Vec<usize>> {
+        let mut consumers = vec![Vec::new(); self.names.len()];
+        for (g, gate) in self.gates.iter().enumerate() {
+            for input in gate.inputs {
+                consumers[input].push(g);
+            }
+        }
+        consumers
+    }
+
+    // Gates ordered so each comes after the gates driving its inputs, or None
+    // if there is a loop.
+    pub fn topological_order(&self) -> Option<Vec<usize>> {
+        let drivers = self.drivers();
+        let consumers = self.consumers();
+        let mut waiting = self
+            .gates
+            .iter()
+            .map(|g| g.inputs.iter().filter(|&&w| drivers[w].is_some()).count())
+            .collect::<Vec<_>>();
+        let mut order = (0..self.gates.len())
+            .filter(|&g| waiting[g] == 0)
+            .collect::<Vec<_>>();
+        let mut next = 0;
+        while next < order.len() {
+            let output = self.gates[order[next]].output;
+            for &c in &consumers[output] {
+                for &input in &self.gates[c].inputs {
+                    if input == output {
+                        waiting[c] -= 1;
+                        if waiting[c] == 0 {
+                            order.push(c);
+                        }
+                    }
+                }
+            }
+            next += 1;
+        }
+        (order.len() == self.gates.len()).then_some(order)
+    }
+
+    // The value of every wire, None where an input was never given. Returns
+    // None if the gates form a loop.
+    pub fn evaluate(&self) -> Option<Vec<Option<bool>>> {
+        let mut values = self.initial.clone();
+        for g in self.topological_order()? {
+            let Gate { op, inputs, output } = self.gates[g];
+            values[output] = match (values[inputs[0]], values[inputs[1]]) {
+                (Some(a), Some(b)) => Some(op.apply(a, b)),
+                _ => None,
+            };
+        }
+        Some(values)
+    }
+
+    // Swap the wires driven by two gates.
+    pub fn swap_outputs(&mut self, a: WireId, b: WireId) {
+        for gate in self.gates.iter_mut() {
+            if gate.output == a {
+                gate.output = b;
+            } else if gate.output == b {
+                gate.output = a;
+            }
+        }
+    }
+
+    // Feed x and y to the circuit, reading the result from z.
+    pub fn add(&mut self, x: u64, y: u64) -> Option<u64> {
+        self.set_number("x", x);
+        self.set_number("y", y);
+        let values = self.evaluate()?;
+        self.number(&values, "z")
+    }
+
+    pub fn to_dot(&self, name: &str) -> Graph {
+        let mut stmts = Vec::new();
+        for (i, gate) in self.gates.iter().enumerate() {
+            let op_name = format!("{}{i}", gate.op.name());
+            let op_label = format!("<{}<SUB>{i}</SUB>>", gate.op.name());
+            stmts.push(Stmt::Node(node!(op_name; attr!("label", op_label))));
+            for input in gate.inputs {
+                stmts.push(Stmt::Edge(
+                    edge!(node_id!(self.names[input]) => node_id!(op_name)),
+                ));
+            }
+            stmts.push(Stmt::Edge(
+                edge!(node_id!(op_name) => node_id!(self.names[gate.output])),
+            ));
+        }
+        Graph::DiGraph {
+            id: id!(name),
+            strict: false,
+            stmts,
+        }
+    }
+
+    // Check the circuit against a ripple-carry adder of x and y into z, where
+    // for bit i with carry in c:
+    //   s = x ^ y, z = s ^ c, and the carry out is (x & y) | (s & c)
+    // Each gate's role follows from its op and inputs, and each role has a
+    // known set of consumers. Returns the names of the wires which don't fit,
+    // which for swapped outputs are the wires that were swapped.
+    pub fn miswired_adder(&self) -> Vec<String> {
+        let consumers = self.consumers();
+        let xs = self.bus("x");
+        let inputs = xs
+            .iter()
+            .chain(&self.bus("y"))
+            .copied()
+            .collect::<HashSet<_>>();
+        let zs = self.bus("z");
+        let last_z = zs.last().copied();
+        let is_input = |w: WireId| inputs.contains(&w);
+        let is_z = |w: WireId| zs.contains(&w);
+        let feeds = |w: WireId, op: Op| consumers[w].iter().any(|&c| self.gates[c].op == op);
+        let first = |g: &Gate| xs.first().is_some_and(|&x0| g.inputs.contains(&x0));
+
+        let mut wrong = BTreeSet::new();
+        for gate in &self.gates {
+            let out = gate.output;
+            let from_inputs = gate.inputs.iter().all(|&w| is_input(w));
+            let fits = match gate.op {
+                // Only the final carry out drives z
+                _ if Some(out) == last_z && xs.len() > 1 => gate.op == Op::Or,
+                Op::Xor if from_inputs && first(gate) => is_z(out),
+                // The half sum goes on to both the sum and the carry
+                Op::Xor if from_inputs => !is_z(out) && feeds(out, Op::Xor) && feeds(out, Op::And),
+                Op::Xor => is_z(out),
+                // The first carry has no carry in to combine with
+                Op::And if first(gate) => !is_z(out) && feeds(out, Op::Xor),
+                Op::And => !is_z(out) && feeds(out, Op::Or),
+                Op::Or => !is_z(out) && feeds(out, Op::Xor) && feeds(out, Op::And),
+            };
+            if !fits {
+                wrong.insert(self.names[out].clone());
+            }
+        }
+        wrong.into_iter().collect()
+    }
+}
+
+impl Display for Circuit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (id, value) in self.initial.iter().enumerate() {
+            if let Some(v) = value {
+                writeln!(f, "{}: {}", self.names[id], *v as u8)?;
+            }
+        }
+        for gate in &self.gates {
+            writeln!(
+                f,
+                "{} {} {} -> {}",
+                self.names[gate.inputs[0]],
+                gate.op.name(),
+                self.names[gate.inputs[1]],
+                self.names[gate.output]
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use graphviz_rust::printer::{DotPrinter, PrinterContext};
+
+    fn ripple_carry(bits: usize) -> Circuit {
+        let mut c = Circuit::new();
+        let bit = |c: char, i: usize| format!("{c}{i:02}");
+        let mut carry = String::new();
+        for i in 0..bits {
+            let (s, a, b, o) = (
+                format!("s{i}"),
+                format!("a{i}"),
+                format!("b{i}"),
+                format!("c{i}"),
+            );
+            if i == 0 {
+                c.add_gate(&bit('x', i), Op::Xor, &bit('y', i), &bit('z', i));
+                c.add_gate(&bit('x', i), Op::And, &bit('y', i), &o);
+            } else {
+                c.add_gate(&bit('x', i), Op::Xor, &bit('y', i), &s);
+                c.add_gate(&s, Op::Xor, &carry, &bit('z', i));
+                c.add_gate(&bit('x', i), Op::And, &bit('y', i), &a);
+                c.add_gate(&s, Op::And, &carry, &b);
+                let out = if i + 1 == bits { bit('z', bits) } else { o };
+                c.add_gate(&a, Op::Or, &b, &out);
+            }
+            carry = format!("c{i}");
+        }
+        c
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let input = "x00: 1
+x01: 0
+x02: 1
+x03: 1
+x04: 0
+y00: 1
+y01: 1
+y02: 1
+y03: 1
+y04: 1
+
+ntg XOR fgs -> mjb
+y02 OR x01 -> tnw
+kwq OR kpj -> z05
+x00 OR x03 -> fst
+tgd XOR rvg -> z01
+vdt OR tnw -> bfw
+bfw AND frj -> z10
+ffh OR nrd -> bqk
+y00 AND y03 -> djm
+y03 OR y00 -> psh
+bqk OR frj -> z08
+tnw OR fst -> frj
+gnj AND tgd -> z11
+bfw XOR mjb -> z00
+x03 OR x00 -> vdt
+gnj AND wpb -> z02
+x04 AND y00 -> kjc
+djm OR pbm -> qhw
+nrd AND vdt -> hwm
+kjc AND fst -> rvg
+y04 OR y02 -> fgs
+y01 AND x02 -> pbm
+ntg OR kjc -> kwq
+psh XOR fgs -> tgd
+qhw XOR tgd -> z09
+pbm OR djm -> kpj
+x03 XOR y03 -> ffh
+x00 XOR y04 -> ntg
+bfw OR bqk -> z06
+nrd XOR fgs -> wpb
+frj XOR qhw -> z04
+bqk OR frj -> z07
+y03 OR x01 -> nrd
+hwm AND bqk -> z03
+tgd XOR rvg -> z12
+tnw OR pbm -> gnj";
+        let circuit = Circuit::parse(input).unwrap();
+        let values = circuit.evaluate().unwrap();
+        assert_eq!(circuit.number(&values, "z"), Some(2024));
+        let reparsed = Circuit::parse(&circuit.to_string()).unwrap();
+        assert_eq!(reparsed.evaluate().unwrap(), values);
+
+        let mut looped = Circuit::parse("a AND b -> c\nc OR d -> b").unwrap();
+        assert!(looped.evaluate().is_none());
+        looped.swap_outputs(looped.wire("c").unwrap(), looped.wire("b").unwrap());
+        assert!(looped.topological_order().is_none());
+        assert!(Circuit::parse("a NAND b -> c").is_none());
+    }
+
+    #[test]
+    fn test_adder() {
+        let mut adder = ripple_carry(8);
+        assert_eq!(adder.add(200, 100), Some(300));
+        assert!(adder.miswired_adder().is_empty());
+
+        for (a, b) in [("z03", "s5"), ("a2", "s2"), ("c4", "z07"), ("s6", "b6")] {
+            adder.swap_outputs(adder.wire(a).unwrap(), adder.wire(b).unwrap());
+        }
+        assert_ne!(adder.add(200, 100), Some(300));
+        assert_eq!(
+            adder.miswired_adder(),
+            ["a2", "b6", "c4", "s2", "s5", "s6", "z03", "z07"]
+        );
+
+        let dot = adder.to_dot("adder").print(&mut PrinterContext::default());
+        assert!(dot.contains("XOR0[label=<XOR<SUB>0</SUB>>]"));
+        assert!(dot.contains("x00 -> XOR0"));
+    }
+
+    #[test]
+    fn test_adder_wire_names() {
+        // Internal wires whose names start like the buses but aren't on them
+        let mut adder = ripple_carry(8);
+        adder.swap_outputs(adder.wire("c4").unwrap(), adder.wire("z07").unwrap());
+        let renamed = adder
+            .to_string()
+            .lines()
+            .map(|line| {
+                line.split(' ')
+                    .map(|w| match w.chars().next() {
+                        Some('s' | 'c') => format!("z{w}"),
+                        Some('a') => format!("x{w}"),
+                        Some('b') => format!("y{w}"),
+                        _ => w.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let renamed = Circuit::parse(&renamed).unwrap();
+        assert_eq!(renamed.miswired_adder(), ["z07", "zc4"]);
+    }
+}
@@ -0,0 +1,319 @@
+// A circular sequence that supports moving elements around in O(log n).
+// Elements are identified by their index in the original values, and are
+// stored in an implicit treap (a randomised balanced tree ordered by position)
+// with parent links, so an element's current position can be found from its id.
+
+#[derive(Debug, Clone)]
+struct Node {
+    left: Option<usize>,
+    right: Option<usize>,
+    parent: Option<usize>,
+    size: usize,
+    priority: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct CircularList<T> {
+    values: Vec<T>,
+    nodes: Vec<Node>,
+    root: Option<usize>,
+}
+
+// splitmix64, so priorities are random looking but repeatable
+fn priority(id: usize) -> u64 {
+    let mut z = (id as u64).wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+impl<T> CircularList<T> {
+    pub fn new(values: Vec<T>) -> CircularList<T> {
+        let nodes = (0..values.len())
+            .map(|id| Node {
+                left: None,
+                right: None,
+                parent: None,
+                size: 1,
+                priority: priority(id),
+            })
+            .collect();
+        let mut list = CircularList {
+            values,
+            nodes,
+            root: None,
+        };
+        for id in 0..list.values.len() {
+            list.root = list.merge(list.root, Some(id));
+        }
+        list.detach(list.root);
+        list
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn value(&self, id: usize) -> &T {
+        &self.values[id]
+    }
+
+    fn size(&self, n: Option<usize>) -> usize {
+        n.map_or(0, |n| self.nodes[n].size)
+    }
+
+    fn update(&mut self, n: usize) {
+        let (left, right) = (self.nodes[n].left, self.nodes[n].right);
+        self.nodes[n].size = 1 + self.size(left) + self.size(right);
+        for child in [left, right].into_iter().flatten() {
+            self.nodes[child].parent = Some(n);
+        }
+    }
+
+    fn detach(&mut self, n: Option<usize>) {
+        if let Some(n) = n {
+            self.nodes[n].parent = None;
+        }
+    }
+
+    fn merge(&mut self, a: Option<usize>, b: Option<usize>) -> Option<usize> {
+        match (a, b) {
+            (None, t) | (t, None) => t,
+            (Some(a), Some(b)) => {
+                if self.nodes[a].priority > self.nodes[b].priority {
+                    let right = self.nodes[a].right;
+                    self.nodes[a].right = self.merge(right, Some(b));
+                    self.update(a);
+                    Some(a)
+                } else {
+                    let left = self.nodes[b].left;
+                    self.nodes[b].left = self.merge(Some(a), left);
+                    self.update(b);
+                    Some(b)
+                }
+            }
+        }
+    }
+
+    // Split off the first k elements
+    fn split(&mut self, t: Option<usize>, k: usize) -> (Option<usize>, Option<usize>) {
+        let Some(n) = t else {
+            return (None, None);
+        };
+        let left = self.nodes[n].left;
+        let left_size = self.size(left);
+        if k <= left_size {
+            let (a, b) = self.split(left, k);
+            self.nodes[n].left = b;
+            self.update(n);
+            self.detach(a);
+            (a, Some(n))
+        } else {
+            let right = self.nodes[n].right;
+            let (a, b) = self.split(right, k - left_size - 1);
+            self.nodes[n].right = a;
+            self.update(n);
+            self.detach(b);
+            (Some(n), b)
+        }
+    }
+
+    // The current position of an element, counting from the start of the
+    // underlying sequence.
+    pub fn position(&self, id: usize) -> usize {
+        let mut pos = self.size(self.nodes[id].left);
+        let mut n = id;
+        while let Some(p) = self.nodes[n].parent {
+            if self.nodes[p].right == Some(n) {
+                pos += self.size(self.nodes[p].left) + 1;
+            }
+            n = p;
+        }
+        pos
+    }
+
+    // The id of the element at a position, modulo the length, or None if the
+    // list is empty.
+    pub fn id_at(&self, pos: usize) -> Option<usize> {
+        let mut pos = pos.checked_rem(self.len())?;
+        let mut n = self.root?;
+        loop {
+            let left = self.size(self.nodes[n].left);
+            if pos < left {
+                n = self.nodes[n].left.unwrap();
+            } else if pos == left {
+                return Some(n);
+            } else {
+                pos -= left + 1;
+                n = self.nodes[n].right.unwrap();
+            }
+        }
+    }
+
+    pub fn get(&self, pos: usize) -> Option<&T> {
+        self.id_at(pos).map(|id| &self.values[id])
+    }
+
+    fn remove(&mut self, id: usize) {
+        let pos = self.position(id);
+        let (a, b) = self.split(self.root, pos);
+        let (_, c) = self.split(b, 1);
+        self.root = self.merge(a, c);
+        self.detach(self.root);
+    }
+
+    fn insert(&mut self, id: usize, pos: usize) {
+        let (a, b) = self.split(self.root, pos);
+        let a = self.merge(a, Some(id));
+        self.root = self.merge(a, b);
+        self.detach(self.root);
+    }
+
+    // Move an element k places along, wrapping around the others.
+    pub fn move_by(&mut self, id: usize, k: i64) {
+        let others = self.len() as i64 - 1;
+        if others < 1 {
+            return;
+        }
+        let pos = self.position(id) as i64;
+        self.remove(id);
+        self.insert(id, (pos + k).rem_euclid(others) as usize);
+    }
+
+    // Move an element to just after another one.
+    pub fn move_after(&mut self, id: usize, after: usize) {
+        if id == after {
+            return;
+        }
+        self.remove(id);
+        let pos = self.position(after) + 1;
+        self.insert(id, pos);
+    }
+
+    // The element after this one, wrapping around at the end.
+    pub fn next(&self, id: usize) -> usize {
+        let mut n = id;
+        if let Some(mut r) = self.nodes[n].right {
+            while let Some(l) = self.nodes[r].left {
+                r = l;
+            }
+            return r;
+        }
+        while let Some(p) = self.nodes[n].parent {
+            if self.nodes[p].left == Some(n) {
+                return p;
+            }
+            n = p;
+        }
+        // Holding id, the list can't be empty
+        self.id_at(0).unwrap()
+    }
+
+    // Once around the circle, starting with the given element.
+    pub fn ids_from(&self, id: usize) -> impl Iterator<Item = usize> + '_ {
+        std::iter::successors(Some(id), move |&n| Some(self.next(n))).take(self.len())
+    }
+
+    pub fn iter_from(&self, id: usize) -> impl Iterator<Item = &T> + '_ {
+        self.ids_from(id).map(|n| &self.values[n])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.id_at(0).into_iter().flat_map(|id| self.iter_from(id))
+    }
+}
+
+impl<T> FromIterator<T> for CircularList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        CircularList::new(iter.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mix(values: &[i64], key: i64, rounds: usize) -> i64 {
+        let mut list = values.iter().map(|v| v * key).collect::<CircularList<_>>();
+        for _ in 0..rounds {
+            for id in 0..list.len() {
+                list.move_by(id, *list.value(id));
+            }
+        }
+        let zero = values.iter().position(|&v| v == 0).unwrap();
+        let pos = list.position(zero);
+        [1000, 2000, 3000]
+            .iter()
+            .map(|k| list.get(pos + k).unwrap())
+            .sum()
+    }
+
+    #[test]
+    fn test_mixing() {
+        let values = [1, 2, -3, 3, -2, 0, 4];
+        let mut list = values.iter().copied().collect::<CircularList<i64>>();
+        list.move_by(0, 1);
+        list.move_by(1, 2);
+        assert_eq!(
+            list.iter().copied().collect::<Vec<_>>(),
+            [1, -3, 2, 3, -2, 0, 4]
+        );
+        list.move_by(2, -3);
+        assert_eq!(
+            list.iter_from(0).copied().collect::<Vec<_>>(),
+            [1, 2, 3, -2, -3, 0, 4]
+        );
+
+        assert_eq!(list.id_at(7), Some(0));
+        assert_eq!(list.get(12), Some(&0));
+
+        assert_eq!(mix(&values, 1, 1), 3);
+        assert_eq!(mix(&values, 811589153, 10), 1623178306);
+    }
+
+    #[test]
+    fn test_empty() {
+        let list = CircularList::<i64>::new(Vec::new());
+        assert!(list.is_empty());
+        assert_eq!(list.id_at(0), None);
+        assert_eq!(list.get(3), None);
+        assert_eq!(list.iter().count(), 0);
+    }
+
+    #[test]
+    fn test_crab_cups() {
+        let play = |moves: usize| {
+            let labels = [3, 8, 9, 1, 2, 5, 4, 6, 7];
+            let mut cups = labels.iter().copied().collect::<CircularList<usize>>();
+            let mut id_of = vec![0; labels.len() + 1];
+            labels.iter().enumerate().for_each(|(id, &l)| id_of[l] = id);
+            let mut current = 0;
+            for _ in 0..moves {
+                let picked = cups.ids_from(current).skip(1).take(3).collect::<Vec<_>>();
+                let mut dest = *cups.value(current);
+                loop {
+                    dest = if dest == 1 { labels.len() } else { dest - 1 };
+                    if picked.iter().all(|&p| *cups.value(p) != dest) {
+                        break;
+                    }
+                }
+                let mut after = id_of[dest];
+                for p in picked {
+                    cups.move_after(p, after);
+                    after = p;
+                }
+                current = cups.next(current);
+            }
+            cups.iter_from(id_of[1])
+                .skip(1)
+                .map(|c| c.to_string())
+                .collect::<String>()
+        };
+        assert_eq!(play(10), "92658374");
+        assert_eq!(play(100), "67384529");
+    }
+}
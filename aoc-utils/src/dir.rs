@@ -209,6 +209,226 @@ impl From<Dir4> for usize {
     }
 }
 
+// Hex directions for a pointy topped layout, using axial (q, r) vectors.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Sequence)]
+pub enum DirHexPointy {
+    NE,
+    E,
+    SE,
+    SW,
+    W,
+    NW,
+}
+
+impl DirHexPointy {
+    const VEC_NE: DirVec = Vec2d { x: 1, y: -1 };
+    const VEC_E: DirVec = Vec2d { x: 1, y: 0 };
+    const VEC_SE: DirVec = Vec2d { x: 0, y: 1 };
+    const VEC_SW: DirVec = Vec2d { x: -1, y: 1 };
+    const VEC_W: DirVec = Vec2d { x: -1, y: 0 };
+    const VEC_NW: DirVec = Vec2d { x: 0, y: -1 };
+
+    pub fn parse(s: &str) -> Option<DirHexPointy> {
+        match s {
+            "ne" => Some(DirHexPointy::NE),
+            "e" => Some(DirHexPointy::E),
+            "se" => Some(DirHexPointy::SE),
+            "sw" => Some(DirHexPointy::SW),
+            "w" => Some(DirHexPointy::W),
+            "nw" => Some(DirHexPointy::NW),
+            _ => None,
+        }
+    }
+}
+
+impl Dir for DirHexPointy {
+    type DirType = Self;
+
+    fn cw() -> All<DirHexPointy> {
+        all::<DirHexPointy>()
+    }
+
+    fn ccw() -> ReverseAll<DirHexPointy> {
+        reverse_all::<DirHexPointy>()
+    }
+
+    fn rotate_cw(&self) -> DirHexPointy {
+        next_cycle::<DirHexPointy>(self)
+    }
+
+    fn rotate_ccw(&self) -> DirHexPointy {
+        previous_cycle::<DirHexPointy>(self)
+    }
+
+    fn from_i(i: usize) -> DirHexPointy {
+        match i % 6 {
+            0 => DirHexPointy::NE,
+            1 => DirHexPointy::E,
+            2 => DirHexPointy::SE,
+            3 => DirHexPointy::SW,
+            4 => DirHexPointy::W,
+            5 => DirHexPointy::NW,
+            _ => panic!(),
+        }
+    }
+
+    fn to_i(&self) -> usize {
+        match self {
+            DirHexPointy::NE => 0,
+            DirHexPointy::E => 1,
+            DirHexPointy::SE => 2,
+            DirHexPointy::SW => 3,
+            DirHexPointy::W => 4,
+            DirHexPointy::NW => 5,
+        }
+    }
+
+    fn from_vec2d(v: DirVec) -> Self {
+        match v {
+            Self::VEC_NE => DirHexPointy::NE,
+            Self::VEC_E => DirHexPointy::E,
+            Self::VEC_SE => DirHexPointy::SE,
+            Self::VEC_SW => DirHexPointy::SW,
+            Self::VEC_W => DirHexPointy::W,
+            Self::VEC_NW => DirHexPointy::NW,
+            _ => panic!("Invalid vector for DirHexPointy"),
+        }
+    }
+
+    fn to_vec2d(&self) -> DirVec {
+        match self {
+            DirHexPointy::NE => Self::VEC_NE,
+            DirHexPointy::E => Self::VEC_E,
+            DirHexPointy::SE => Self::VEC_SE,
+            DirHexPointy::SW => Self::VEC_SW,
+            DirHexPointy::W => Self::VEC_W,
+            DirHexPointy::NW => Self::VEC_NW,
+        }
+    }
+}
+
+impl From<usize> for DirHexPointy {
+    fn from(i: usize) -> DirHexPointy {
+        DirHexPointy::from_i(i)
+    }
+}
+
+impl From<DirHexPointy> for usize {
+    fn from(dir: DirHexPointy) -> usize {
+        dir.to_i()
+    }
+}
+
+// Hex directions for a flat topped layout, using axial (q, r) vectors.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Sequence)]
+pub enum DirHexFlat {
+    N,
+    NE,
+    SE,
+    S,
+    SW,
+    NW,
+}
+
+impl DirHexFlat {
+    const VEC_N: DirVec = Vec2d { x: 0, y: -1 };
+    const VEC_NE: DirVec = Vec2d { x: 1, y: -1 };
+    const VEC_SE: DirVec = Vec2d { x: 1, y: 0 };
+    const VEC_S: DirVec = Vec2d { x: 0, y: 1 };
+    const VEC_SW: DirVec = Vec2d { x: -1, y: 1 };
+    const VEC_NW: DirVec = Vec2d { x: -1, y: 0 };
+
+    pub fn parse(s: &str) -> Option<DirHexFlat> {
+        match s {
+            "n" => Some(DirHexFlat::N),
+            "ne" => Some(DirHexFlat::NE),
+            "se" => Some(DirHexFlat::SE),
+            "s" => Some(DirHexFlat::S),
+            "sw" => Some(DirHexFlat::SW),
+            "nw" => Some(DirHexFlat::NW),
+            _ => None,
+        }
+    }
+}
+
+impl Dir for DirHexFlat {
+    type DirType = Self;
+
+    fn cw() -> All<DirHexFlat> {
+        all::<DirHexFlat>()
+    }
+
+    fn ccw() -> ReverseAll<DirHexFlat> {
+        reverse_all::<DirHexFlat>()
+    }
+
+    fn rotate_cw(&self) -> DirHexFlat {
+        next_cycle::<DirHexFlat>(self)
+    }
+
+    fn rotate_ccw(&self) -> DirHexFlat {
+        previous_cycle::<DirHexFlat>(self)
+    }
+
+    fn from_i(i: usize) -> DirHexFlat {
+        match i % 6 {
+            0 => DirHexFlat::N,
+            1 => DirHexFlat::NE,
+            2 => DirHexFlat::SE,
+            3 => DirHexFlat::S,
+            4 => DirHexFlat::SW,
+            5 => DirHexFlat::NW,
+            _ => panic!(),
+        }
+    }
+
+    fn to_i(&self) -> usize {
+        match self {
+            DirHexFlat::N => 0,
+            DirHexFlat::NE => 1,
+            DirHexFlat::SE => 2,
+            DirHexFlat::S => 3,
+            DirHexFlat::SW => 4,
+            DirHexFlat::NW => 5,
+        }
+    }
+
+    fn from_vec2d(v: DirVec) -> Self {
+        match v {
+            Self::VEC_N => DirHexFlat::N,
+            Self::VEC_NE => DirHexFlat::NE,
+            Self::VEC_SE => DirHexFlat::SE,
+            Self::VEC_S => DirHexFlat::S,
+            Self::VEC_SW => DirHexFlat::SW,
+            Self::VEC_NW => DirHexFlat::NW,
+            _ => panic!("Invalid vector for DirHexFlat"),
+        }
+    }
+
+    fn to_vec2d(&self) -> DirVec {
+        match self {
+            DirHexFlat::N => Self::VEC_N,
+            DirHexFlat::NE => Self::VEC_NE,
+            DirHexFlat::SE => Self::VEC_SE,
+            DirHexFlat::S => Self::VEC_S,
+            DirHexFlat::SW => Self::VEC_SW,
+            DirHexFlat::NW => Self::VEC_NW,
+        }
+    }
+}
+
+impl From<usize> for DirHexFlat {
+    fn from(i: usize) -> DirHexFlat {
+        DirHexFlat::from_i(i)
+    }
+}
+
+impl From<DirHexFlat> for usize {
+    fn from(dir: DirHexFlat) -> usize {
+        dir.to_i()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -219,4 +439,19 @@ mod test {
         assert_eq!(Dir4::S.to_vec2d(), Vec2d { x: 0, y: 1 });
         assert_eq!(Dir4::W.to_vec2d(), Vec2d { x: -1, y: 0 });
     }
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(DirHexPointy::NE.rotate_cw(), DirHexPointy::E);
+        assert_eq!(DirHexPointy::NE.rotate_ccw(), DirHexPointy::NW);
+        assert_eq!(DirHexFlat::from_i(9), DirHexFlat::S);
+        assert_eq!(DirHexFlat::parse("nw"), Some(DirHexFlat::NW));
+        for d in DirHexPointy::cw() {
+            let opposite = DirHexPointy::from_i(d.to_i() + 3);
+            assert_eq!(d.to_vec2d() + opposite.to_vec2d(), Vec2d::new(0, 0));
+        }
+        assert_eq!(DirHexPointy::from(7), DirHexPointy::E);
+        assert_eq!(usize::from(DirHexFlat::SW), 4);
+        assert!(DirHexFlat::cw().all(|d| DirHexFlat::from(usize::from(d)) == d));
+    }
 }
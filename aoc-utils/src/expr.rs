@@ -0,0 +1,455 @@
+use std::collections::{HashMap, HashSet};
+
+use num_traits::{One, Zero};
+
+use crate::linalg::rational::Rational;
+
+pub type Value = Rational<i128>;
+pub type ExprId = usize;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl Op {
+    pub fn parse(s: &str) -> Option<Op> {
+        match s {
+            "+" => Some(Op::Add),
+            "-" => Some(Op::Sub),
+            "*" => Some(Op::Mul),
+            "/" => Some(Op::Div),
+            _ => None,
+        }
+    }
+
+    pub fn symbol(&self) -> char {
+        match self {
+            Op::Add => '+',
+            Op::Sub => '-',
+            Op::Mul => '*',
+            Op::Div => '/',
+        }
+    }
+
+    pub fn apply(&self, a: Value, b: Value) -> Option<Value> {
+        match self {
+            Op::Add => Some(a + b),
+            Op::Sub => Some(a - b),
+            Op::Mul => Some(a * b),
+            Op::Div if b.is_zero() => None,
+            Op::Div => Some(a / b),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    // The line, counting from 0, isn't a definition
+    BadLine(usize),
+    UnknownName(String),
+    // A name defined more than once
+    Duplicate(String),
+    // A definition which depends on itself, by name
+    Cycle(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node {
+    Const(Value),
+    Var(String),
+    Binary(Op, ExprId, ExprId),
+}
+
+// A DAG of expression nodes, some of which are named. Values are exact
+// rationals, so division never loses information.
+#[derive(Debug, Clone, Default)]
+pub struct Expr {
+    nodes: Vec<Node>,
+    names: HashMap<String, ExprId>,
+}
+
+impl Expr {
+    pub fn new() -> Expr {
+        Expr::default()
+    }
+
+    // Parse definitions like "root: pppw + sjmn" and "dbpl: 5", in any order.
+    pub fn parse(input: &str) -> Result<Expr, ParseError> {
+        let defs = input
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                let (name, def) = line.split_once(':').ok_or(ParseError::BadLine(i))?;
+                Ok((i, name.trim(), def))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut names = HashMap::new();
+        for (id, (_, name, _)) in defs.iter().enumerate() {
+            if names.insert(name.to_string(), id).is_some() {
+                return Err(ParseError::Duplicate(name.to_string()));
+            }
+        }
+        let lookup = |name: &str| {
+            names
+                .get(name)
+                .copied()
+                .ok_or_else(|| ParseError::UnknownName(name.to_string()))
+        };
+        let nodes = defs
+            .iter()
+            .map(|&(i, _, def)| {
+                let parts = def.split_whitespace().collect::<Vec<_>>();
+                let bad = ParseError::BadLine(i);
+                match parts[..] {
+                    [v] => Ok(Node::Const(Value::from(
+                        v.parse::<i128>().map_err(|_| bad)?,
+                    ))),
+                    [a, op, b] => Ok(Node::Binary(
+                        Op::parse(op).ok_or(bad)?,
+                        lookup(a)?,
+                        lookup(b)?,
+                    )),
+                    _ => Err(bad),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let expr = Expr { nodes, names };
+        match expr.find_cycle() {
+            Some(id) => Err(ParseError::Cycle(defs[id].1.to_string())),
+            None => Ok(expr),
+        }
+    }
+
+    // A node which depends on itself, if there is one.
+    fn find_cycle(&self) -> Option<ExprId> {
+        // Nodes whose operands are all done, and those still being visited
+        let (mut done, mut visiting) = (HashSet::new(), HashSet::new());
+        for root in 0..self.nodes.len() {
+            let mut stack = vec![(root, false)];
+            while let Some((id, expanded)) = stack.pop() {
+                if expanded {
+                    visiting.remove(&id);
+                    done.insert(id);
+                    continue;
+                }
+                if done.contains(&id) {
+                    continue;
+                }
+                if !visiting.insert(id) {
+                    return Some(id);
+                }
+                stack.push((id, true));
+                if let Some((a, b)) = self.operands(id) {
+                    stack.extend([(a, false), (b, false)]);
+                }
+            }
+        }
+        None
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn node(&self, id: ExprId) -> &Node {
+        &self.nodes[id]
+    }
+
+    pub fn id(&self, name: &str) -> Option<ExprId> {
+        self.names.get(name).copied()
+    }
+
+    pub fn push(&mut self, node: Node) -> ExprId {
+        self.nodes.push(node);
+        self.nodes.len() - 1
+    }
+
+    pub fn define(&mut self, name: &str, node: Node) -> ExprId {
+        let id = self.push(node);
+        self.names.insert(name.to_string(), id);
+        id
+    }
+
+    // Turn a named node into an unknown.
+    pub fn set_unknown(&mut self, name: &str) -> Option<ExprId> {
+        let id = self.id(name)?;
+        self.nodes[id] = Node::Var(name.to_string());
+        Some(id)
+    }
+
+    pub fn operands(&self, id: ExprId) -> Option<(ExprId, ExprId)> {
+        match self.nodes[id] {
+            Node::Binary(_, a, b) => Some((a, b)),
+            _ => None,
+        }
+    }
+
+    fn eval_memo(&self, id: ExprId, memo: &mut HashMap<ExprId, Option<Value>>) -> Option<Value> {
+        if let Some(&v) = memo.get(&id) {
+            return v;
+        }
+        let v = match self.nodes[id] {
+            Node::Const(v) => Some(v),
+            Node::Var(_) => None,
+            Node::Binary(op, a, b) => {
+                let a = self.eval_memo(a, memo);
+                let b = self.eval_memo(b, memo);
+                a.zip(b).and_then(|(a, b)| op.apply(a, b))
+            }
+        };
+        memo.insert(id, v);
+        v
+    }
+
+    // The value of a node, or None if it depends on an unknown or divides by
+    // zero.
+    pub fn eval(&self, id: ExprId) -> Option<Value> {
+        self.eval_memo(id, &mut HashMap::new())
+    }
+
+    // Replace every node that doesn't depend on an unknown with its value.
+    // Returns how many nodes were folded.
+    pub fn fold_constants(&mut self) -> usize {
+        let mut memo = HashMap::new();
+        let mut folded = 0;
+        for id in 0..self.nodes.len() {
+            if let (Node::Binary(..), Some(v)) = (&self.nodes[id], self.eval_memo(id, &mut memo)) {
+                self.nodes[id] = Node::Const(v);
+                folded += 1;
+            }
+        }
+        folded
+    }
+
+    fn linear_memo(
+        &self,
+        id: ExprId,
+        unknown: &str,
+        memo: &mut HashMap<ExprId, Option<(Value, Value)>>,
+    ) -> Option<(Value, Value)> {
+        if let Some(&v) = memo.get(&id) {
+            return v;
+        }
+        let v = match &self.nodes[id] {
+            Node::Const(v) => Some((Value::zero(), *v)),
+            Node::Var(name) if name == unknown => Some((Value::one(), Value::zero())),
+            Node::Var(_) => None,
+            &Node::Binary(op, a, b) => {
+                let l = self.linear_memo(a, unknown, memo);
+                let r = self.linear_memo(b, unknown, memo);
+                l.zip(r).and_then(|((a1, b1), (a2, b2))| match op {
+                    Op::Add => Some((a1 + a2, b1 + b2)),
+                    Op::Sub => Some((a1 - a2, b1 - b2)),
+                    Op::Mul if a1.is_zero() => Some((b1 * a2, b1 * b2)),
+                    Op::Mul if a2.is_zero() => Some((a1 * b2, b1 * b2)),
+                    Op::Div if a2.is_zero() && !b2.is_zero() => Some((a1 / b2, b1 / b2)),
+                    _ => None,
+                })
+            }
+        };
+        memo.insert(id, v);
+        v
+    }
+
+    // The node as a * unknown + b, if it is linear in the unknown.
+    pub fn linear(&self, id: ExprId, unknown: &str) -> Option<(Value, Value)> {
+        self.linear_memo(id, unknown, &mut HashMap::new())
+    }
+
+    // Solve lhs == rhs for the unknown, when both sides are linear in it.
+    pub fn solve(&self, lhs: ExprId, rhs: ExprId, unknown: &str) -> Option<Value> {
+        let mut memo = HashMap::new();
+        let (a1, b1) = self.linear_memo(lhs, unknown, &mut memo)?;
+        let (a2, b2) = self.linear_memo(rhs, unknown, &mut memo)?;
+        if a1 == a2 {
+            None
+        } else {
+            Some((b2 - b1) / (a1 - a2))
+        }
+    }
+
+    // The node as an expression. Operations used more than once are bound
+    // to their name, or a made up one, in lets before it, so the output stays
+    // the size of the DAG rather than of the tree it unfolds to.
+    pub fn format(&self, id: ExprId) -> String {
+        self.format_all(&[id]).join(" = ")
+    }
+
+    pub fn format_equation(&self, lhs: ExprId, rhs: ExprId) -> String {
+        self.format_all(&[lhs, rhs]).join(" = ")
+    }
+
+    // The roots formatted, with the lets for shared nodes before the first.
+    fn format_all(&self, roots: &[ExprId]) -> Vec<String> {
+        let mut uses = HashMap::new();
+        let mut stack = roots.to_vec();
+        while let Some(id) = stack.pop() {
+            *uses.entry(id).or_insert(0) += 1;
+            if uses[&id] == 1 {
+                if let Some((a, b)) = self.operands(id) {
+                    stack.extend([a, b]);
+                }
+            }
+        }
+        let names = self
+            .names
+            .iter()
+            .map(|(name, &id)| (id, name.as_str()))
+            .collect::<HashMap<_, _>>();
+        let shared = uses
+            .iter()
+            .filter(|&(&id, &n)| n > 1 && self.operands(id).is_some())
+            .map(|(&id, _)| {
+                (
+                    id,
+                    names.get(&id).map_or(format!("_{id}"), |n| n.to_string()),
+                )
+            })
+            .collect::<HashMap<_, _>>();
+        let mut lets = String::new();
+        let mut bound = HashSet::new();
+        let mut formatted = roots
+            .iter()
+            .map(|&id| self.format_shared(id, &shared, &mut bound, &mut lets))
+            .collect::<Vec<_>>();
+        formatted[0] = lets + &formatted[0];
+        formatted
+    }
+
+    fn format_shared(
+        &self,
+        id: ExprId,
+        shared: &HashMap<ExprId, String>,
+        bound: &mut HashSet<ExprId>,
+        lets: &mut String,
+    ) -> String {
+        let name = shared.get(&id);
+        if let Some(name) = name.filter(|_| bound.contains(&id)) {
+            return name.clone();
+        }
+        let formatted = match &self.nodes[id] {
+            Node::Const(v) => v.to_string(),
+            Node::Var(name) => name.clone(),
+            &Node::Binary(op, a, b) => {
+                let a = self.format_shared(a, shared, bound, lets);
+                let b = self.format_shared(b, shared, bound, lets);
+                format!("({a} {} {b})", op.symbol())
+            }
+        };
+        match name {
+            Some(name) => {
+                bound.insert(id);
+                lets.push_str(&format!("let {name} = {formatted};\n"));
+                name.clone()
+            }
+            None => formatted,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MONKEYS: &str = "root: pppw + sjmn
+dbpl: 5
+cczh: sllz + lgvd
+zczc: 2
+ptdq: humn - dvpt
+dvpt: 3
+lfqf: 4
+humn: 5
+ljgn: 2
+sjmn: drzm * dbpl
+sllz: 4
+pppw: cczh / lfqf
+lgvd: ljgn * ptdq
+drzm: hmdt - zczc
+hmdt: 32
+";
+
+    #[test]
+    fn test_monkey_math() {
+        let mut expr = Expr::parse(MONKEYS).unwrap();
+        let root = expr.id("root").unwrap();
+        assert_eq!(expr.eval(root), Some(Value::from(152)));
+
+        expr.set_unknown("humn");
+        assert_eq!(expr.eval(root), None);
+        let (lhs, rhs) = expr.operands(root).unwrap();
+        assert_eq!(expr.solve(lhs, rhs, "humn"), Some(Value::from(301)));
+
+        assert_eq!(expr.fold_constants(), 2);
+        assert_eq!(
+            expr.format_equation(lhs, rhs),
+            "((4 + (2 * (humn - 3))) / 4) = 150"
+        );
+        assert_eq!(expr.solve(lhs, rhs, "humn"), Some(Value::from(301)));
+    }
+
+    #[test]
+    fn test_non_linear() {
+        let mut expr = Expr::new();
+        let x = expr.define("x", Node::Var("x".to_string()));
+        let square = expr.push(Node::Binary(Op::Mul, x, x));
+        let two = expr.push(Node::Const(Value::from(2)));
+        let half = expr.push(Node::Binary(Op::Div, x, two));
+        assert_eq!(expr.solve(square, two, "x"), None);
+        assert_eq!(expr.solve(half, two, "x"), Some(Value::from(4)));
+        assert_eq!(expr.solve(half, x, "x"), Some(Value::zero()));
+        assert_eq!(expr.solve(half, half, "x"), None);
+        assert_eq!(expr.format(half), "(x / 2)");
+        let zero = expr.push(Node::Const(Value::zero()));
+        let undefined = expr.push(Node::Binary(Op::Div, two, zero));
+        assert_eq!(expr.eval(undefined), None);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            Expr::parse("a: 1\n\nb 2").err(),
+            Some(ParseError::BadLine(2))
+        );
+        assert_eq!(Expr::parse("a: 1 ^ 2").err(), Some(ParseError::BadLine(0)));
+        assert_eq!(
+            Expr::parse("a: b + 1").err(),
+            Some(ParseError::UnknownName("b".to_string()))
+        );
+        let cycle = Expr::parse("c: 1\na: b + c\nb: a * c").err();
+        assert!(matches!(cycle, Some(ParseError::Cycle(name)) if name == "a" || name == "b"));
+        assert!(Expr::parse("a: b * b\nb: 3").is_ok());
+        assert_eq!(
+            Expr::parse("a: 1\nb: a + a\na: 2").err(),
+            Some(ParseError::Duplicate("a".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_format_shared() {
+        // Each level squares the one below, so the tree doubles with depth
+        let mut expr = Expr::new();
+        let mut top = expr.define("x", Node::Var("x".to_string()));
+        for _ in 0..64 {
+            top = expr.push(Node::Binary(Op::Mul, top, top));
+        }
+        let formatted = expr.format(top);
+        assert_eq!(formatted.lines().count(), 64);
+        assert!(formatted.ends_with("(_63 * _63)"));
+
+        let mut expr = Expr::parse("a: b + b\nb: c * d\nc: 3\nd: 2").unwrap();
+        let (a, b) = (expr.id("a").unwrap(), expr.id("b").unwrap());
+        assert_eq!(expr.format(a), "let b = (3 * 2);\n(b + b)");
+        assert_eq!(expr.format_equation(b, a), "let b = (3 * 2);\nb = (b + b)");
+        expr.set_unknown("c");
+        assert_eq!(expr.format(a), "let b = (c * 2);\n(b + b)");
+    }
+}
@@ -0,0 +1,261 @@
+pub mod aabb3d;
+pub mod line;
+pub mod rect;
+pub mod segment;
+pub mod taxi_ball;
+
+use num_traits::{PrimInt, Signed};
+
+use crate::{dir::Dir4, math::gcd, pos2d::Pos2d, vec2d::Vec2d};
+use rect::Rect2d;
+use segment::{cross, Segment2d};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Winding {
+    Cw,
+    Ccw,
+    Degenerate,
+}
+
+// A closed polygon on the integer lattice.
+// Coordinates follow the grid convention (y grows downwards), so a polygon
+// walked clockwise on screen has a positive signed area.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Polygon<Scalar: PrimInt + Signed> {
+    vertices: Vec<Pos2d<Scalar>>,
+}
+
+impl<Scalar: PrimInt + Signed> Polygon<Scalar> {
+    pub fn new(vertices: Vec<Pos2d<Scalar>>) -> Polygon<Scalar> {
+        let mut vertices = vertices;
+        if vertices.len() > 1 && vertices.first() == vertices.last() {
+            vertices.pop();
+        }
+        Polygon { vertices }
+    }
+
+    pub fn from_steps(
+        start: Pos2d<Scalar>,
+        steps: impl IntoIterator<Item = (Dir4, Scalar)>,
+    ) -> Polygon<Scalar>
+    where
+        Scalar: From<i64>,
+    {
+        let mut pos = start;
+        let mut vertices = vec![start];
+        for (dir, n) in steps {
+            pos = pos + Vec2d::<Scalar>::from(dir) * n;
+            vertices.push(pos);
+        }
+        Polygon::new(vertices)
+    }
+
+    pub fn vertices(&self) -> &[Pos2d<Scalar>] {
+        &self.vertices
+    }
+
+    pub fn edges(&self) -> impl Iterator<Item = (Pos2d<Scalar>, Pos2d<Scalar>)> + '_ {
+        let n = self.vertices.len();
+        (0..n).map(move |i| (self.vertices[i], self.vertices[(i + 1) % n]))
+    }
+
+    // Twice the signed area (shoelace formula), which is always an integer.
+    pub fn double_signed_area(&self) -> Scalar {
+        self.edges()
+            .fold(Scalar::zero(), |area, (a, b)| area + a.x * b.y - b.x * a.y)
+    }
+
+    // The area, rounded down for polygons with half-integer areas.
+    pub fn area(&self) -> Scalar {
+        self.double_signed_area().abs() / (Scalar::one() + Scalar::one())
+    }
+
+    pub fn winding(&self) -> Winding {
+        let area = self.double_signed_area();
+        if area.is_positive() {
+            Winding::Cw
+        } else if area.is_negative() {
+            Winding::Ccw
+        } else {
+            Winding::Degenerate
+        }
+    }
+
+    pub fn perimeter(&self) -> Scalar {
+        self.edges()
+            .fold(Scalar::zero(), |len, (a, b)| len + a.taxi_distance(&b))
+    }
+
+    // Number of lattice points lying on the boundary.
+    pub fn boundary_points(&self) -> Scalar {
+        self.edges().fold(Scalar::zero(), |count, (a, b)| {
+            count + gcd((b.x - a.x).abs(), (b.y - a.y).abs())
+        })
+    }
+
+    // Number of lattice points strictly inside the polygon (Pick's theorem).
+    //   A = I + B/2 - 1  =>  I = (2A - B + 2) / 2
+    pub fn interior_points(&self) -> Scalar {
+        let two = Scalar::one() + Scalar::one();
+        (self.double_signed_area().abs() - self.boundary_points() + two) / two
+    }
+
+    // Number of lattice points inside or on the boundary. For a polygon traced
+    // through cell centres this is the number of cells it covers.
+    pub fn lattice_points(&self) -> Scalar {
+        self.interior_points() + self.boundary_points()
+    }
+
+    pub fn segments(&self) -> impl Iterator<Item = Segment2d<Scalar>> + '_ {
+        self.edges().map(|(a, b)| Segment2d::new(a, b))
+    }
+
+    // Point in polygon test with the polygon scaled by `scale`, allowing
+    // points at fractional coordinates to be tested exactly.
+    fn contains_scaled(&self, p: &Pos2d<Scalar>, scale: Scalar) -> bool {
+        let mut inside = false;
+        for (a, b) in self.edges() {
+            let a = Pos2d::new(a.x * scale, a.y * scale);
+            let b = Pos2d::new(b.x * scale, b.y * scale);
+            if Segment2d::new(a, b).contains(p) {
+                return true;
+            }
+            if (a.y > p.y) != (b.y > p.y) {
+                let c = cross(&a, &b, p);
+                if (c > Scalar::zero()) == (b.y > a.y) {
+                    inside = !inside;
+                }
+            }
+        }
+        inside
+    }
+
+    // True if the point is inside or on the boundary of the polygon.
+    pub fn contains(&self, p: &Pos2d<Scalar>) -> bool {
+        self.contains_scaled(p, Scalar::one())
+    }
+
+    // True if the whole rectangle (including its boundary) lies within the polygon.
+    pub fn contains_rect(&self, rect: &Rect2d<Scalar>) -> bool {
+        let two = Scalar::one() + Scalar::one();
+        let centre = Pos2d::new(rect.min.x + rect.max.x, rect.min.y + rect.max.y);
+        rect.corners().iter().all(|c| self.contains(c))
+            && self.contains_scaled(&centre, two)
+            && !self.segments().any(|s| rect.interior_intersects(&s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIG_PLAN: [(Dir4, i64); 14] = [
+        (Dir4::E, 6),
+        (Dir4::S, 5),
+        (Dir4::W, 2),
+        (Dir4::S, 2),
+        (Dir4::E, 2),
+        (Dir4::S, 2),
+        (Dir4::W, 5),
+        (Dir4::N, 2),
+        (Dir4::W, 1),
+        (Dir4::N, 2),
+        (Dir4::E, 2),
+        (Dir4::N, 3),
+        (Dir4::W, 2),
+        (Dir4::N, 2),
+    ];
+
+    #[test]
+    fn test_square() {
+        let square = Polygon::new(vec![
+            Pos2d::<i64>::new(0, 0),
+            Pos2d::new(4, 0),
+            Pos2d::new(4, 4),
+            Pos2d::new(0, 4),
+        ]);
+        assert_eq!(square.double_signed_area(), 32);
+        assert_eq!(square.area(), 16);
+        assert_eq!(square.winding(), Winding::Cw);
+        assert_eq!(square.perimeter(), 16);
+        assert_eq!(square.boundary_points(), 16);
+        assert_eq!(square.interior_points(), 9);
+        assert_eq!(square.lattice_points(), 25);
+
+        let reversed = Polygon::new(square.vertices().iter().rev().copied().collect());
+        assert_eq!(reversed.winding(), Winding::Ccw);
+        assert_eq!(reversed.double_signed_area(), -32);
+        assert_eq!(reversed.interior_points(), 9);
+    }
+
+    #[test]
+    fn test_triangle() {
+        let tri = Polygon::new(vec![
+            Pos2d::<i64>::new(0, 0),
+            Pos2d::new(3, 0),
+            Pos2d::new(0, 3),
+            Pos2d::new(0, 0),
+        ]);
+        assert_eq!(tri.vertices().len(), 3);
+        assert_eq!(tri.double_signed_area(), 9);
+        assert_eq!(tri.boundary_points(), 9);
+        assert_eq!(tri.interior_points(), 1);
+    }
+
+    #[test]
+    fn test_from_steps() {
+        let lagoon = Polygon::from_steps(Pos2d::<i64>::new(0, 0), DIG_PLAN);
+        assert_eq!(lagoon.vertices().len(), 14);
+        assert_eq!(lagoon.perimeter(), 38);
+        assert_eq!(lagoon.lattice_points(), 62);
+    }
+
+    #[test]
+    fn test_contains() {
+        let tiles = Polygon::new(vec![
+            Pos2d::<i64>::new(7, 1),
+            Pos2d::new(11, 1),
+            Pos2d::new(11, 7),
+            Pos2d::new(9, 7),
+            Pos2d::new(9, 5),
+            Pos2d::new(2, 5),
+            Pos2d::new(2, 3),
+            Pos2d::new(7, 3),
+        ]);
+        assert!(tiles.contains(&Pos2d::new(8, 2)));
+        assert!(tiles.contains(&Pos2d::new(11, 4)));
+        assert!(tiles.contains(&Pos2d::new(3, 4)));
+        assert!(!tiles.contains(&Pos2d::new(3, 2)));
+        assert!(!tiles.contains(&Pos2d::new(12, 1)));
+
+        let rect = |ax, ay, bx, by| Rect2d::from_corners(Pos2d::new(ax, ay), Pos2d::new(bx, by));
+        assert!(tiles.contains_rect(&rect(9, 5, 2, 3)));
+        assert!(tiles.contains_rect(&rect(7, 1, 11, 5)));
+        assert!(!tiles.contains_rect(&rect(2, 5, 11, 1)));
+        assert!(!tiles.contains_rect(&rect(2, 3, 11, 7)));
+    }
+
+    #[test]
+    fn test_contains_notch() {
+        let u = Polygon::new(vec![
+            Pos2d::<i64>::new(0, 0),
+            Pos2d::new(2, 0),
+            Pos2d::new(2, 4),
+            Pos2d::new(4, 4),
+            Pos2d::new(4, 0),
+            Pos2d::new(6, 0),
+            Pos2d::new(6, 6),
+            Pos2d::new(0, 6),
+        ]);
+        let notch = Rect2d::new(Pos2d::new(2, 0), Pos2d::new(4, 4));
+        assert!(!u.contains_rect(&notch));
+        assert!(u.contains_rect(&Rect2d::new(Pos2d::new(0, 4), Pos2d::new(6, 6))));
+    }
+
+    #[test]
+    fn test_huge() {
+        let steps = DIG_PLAN.map(|(d, n)| (d, n as i128 * 1_000_000_000_000));
+        let lagoon = Polygon::from_steps(Pos2d::<i128>::new(0, 0), steps);
+        assert_eq!(lagoon.area(), 42 * 1_000_000_000_000_000_000_000_000);
+    }
+}
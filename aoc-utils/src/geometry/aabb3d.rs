@@ -0,0 +1,224 @@
+use std::fmt::Display;
+
+use num_traits::{PrimInt, Signed};
+
+use crate::{pos3d::Pos3d, vec3d::Vec3d};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Axis3d {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis3d {
+    pub fn of<Scalar: PrimInt>(&self, p: &Pos3d<Scalar>) -> Scalar {
+        match self {
+            Axis3d::X => p.x,
+            Axis3d::Y => p.y,
+            Axis3d::Z => p.z,
+        }
+    }
+
+    pub fn unit<Scalar: PrimInt + Signed>(&self) -> Vec3d<Scalar> {
+        let (zero, one) = (Scalar::zero(), Scalar::one());
+        match self {
+            Axis3d::X => Vec3d::new(one, zero, zero),
+            Axis3d::Y => Vec3d::new(zero, one, zero),
+            Axis3d::Z => Vec3d::new(zero, zero, one),
+        }
+    }
+
+    pub fn others(&self) -> [Axis3d; 2] {
+        match self {
+            Axis3d::X => [Axis3d::Y, Axis3d::Z],
+            Axis3d::Y => [Axis3d::X, Axis3d::Z],
+            Axis3d::Z => [Axis3d::X, Axis3d::Y],
+        }
+    }
+}
+
+// An axis aligned box of cells covering [min, max] (inclusive on both ends).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Aabb3d<Scalar: PrimInt + Signed> {
+    pub min: Pos3d<Scalar>,
+    pub max: Pos3d<Scalar>,
+}
+
+impl<Scalar: PrimInt + Signed + Display> Display for Aabb3d<Scalar> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}..{}]", self.min, self.max)
+    }
+}
+
+impl<Scalar: PrimInt + Signed> Aabb3d<Scalar> {
+    pub fn new(min: Pos3d<Scalar>, max: Pos3d<Scalar>) -> Aabb3d<Scalar> {
+        Aabb3d { min, max }
+    }
+
+    pub fn from_corners(a: Pos3d<Scalar>, b: Pos3d<Scalar>) -> Aabb3d<Scalar> {
+        Aabb3d {
+            min: Pos3d::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)),
+            max: Pos3d::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)),
+        }
+    }
+
+    pub fn from_points<'a>(points: impl IntoIterator<Item = &'a Pos3d<Scalar>>) -> Option<Self>
+    where
+        Scalar: 'a,
+    {
+        let mut points = points.into_iter();
+        let first = *points.next()?;
+        Some(points.fold(Aabb3d::new(first, first), |b, p| b.including(p)))
+    }
+
+    pub fn including(&self, p: &Pos3d<Scalar>) -> Self {
+        Aabb3d::new(
+            Pos3d::new(
+                self.min.x.min(p.x),
+                self.min.y.min(p.y),
+                self.min.z.min(p.z),
+            ),
+            Pos3d::new(
+                self.max.x.max(p.x),
+                self.max.y.max(p.y),
+                self.max.z.max(p.z),
+            ),
+        )
+    }
+
+    // The number of cells along each axis.
+    pub fn size(&self) -> Vec3d<Scalar> {
+        let one = Scalar::one();
+        Vec3d::new(
+            self.max.x - self.min.x + one,
+            self.max.y - self.min.y + one,
+            self.max.z - self.min.z + one,
+        )
+    }
+
+    pub fn volume(&self) -> Scalar {
+        let size = self.size();
+        size.x * size.y * size.z
+    }
+
+    pub fn contains(&self, p: &Pos3d<Scalar>) -> bool {
+        p.x >= self.min.x
+            && p.x <= self.max.x
+            && p.y >= self.min.y
+            && p.y <= self.max.y
+            && p.z >= self.min.z
+            && p.z <= self.max.z
+    }
+
+    fn overlaps_on(&self, other: &Self, axis: Axis3d) -> bool {
+        axis.of(&self.min) <= axis.of(&other.max) && axis.of(&other.min) <= axis.of(&self.max)
+    }
+
+    pub fn intersects(&self, other: &Self) -> bool {
+        [Axis3d::X, Axis3d::Y, Axis3d::Z]
+            .iter()
+            .all(|&axis| self.overlaps_on(other, axis))
+    }
+
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        if self.intersects(other) {
+            Some(Aabb3d::new(
+                Pos3d::new(
+                    self.min.x.max(other.min.x),
+                    self.min.y.max(other.min.y),
+                    self.min.z.max(other.min.z),
+                ),
+                Pos3d::new(
+                    self.max.x.min(other.max.x),
+                    self.max.y.min(other.max.y),
+                    self.max.z.min(other.max.z),
+                ),
+            ))
+        } else {
+            None
+        }
+    }
+
+    pub fn overlap_volume(&self, other: &Self) -> Scalar {
+        self.intersection(other)
+            .map_or(Scalar::zero(), |overlap| overlap.volume())
+    }
+
+    // True if the boxes overlap when projected along the axis.
+    pub fn shadows(&self, other: &Self, axis: Axis3d) -> bool {
+        axis.others()
+            .iter()
+            .all(|&other_axis| self.overlaps_on(other, other_axis))
+    }
+
+    pub fn translate(&self, v: Vec3d<Scalar>) -> Self {
+        Aabb3d::new(self.min + v, self.max + v)
+    }
+
+    // Move the box towards `floor` along the axis until it rests on the floor or
+    // on top of one of the obstacles below it.
+    pub fn drop_along<'a>(
+        &self,
+        axis: Axis3d,
+        floor: Scalar,
+        obstacles: impl IntoIterator<Item = &'a Aabb3d<Scalar>>,
+    ) -> Self
+    where
+        Scalar: 'a,
+    {
+        let bottom = axis.of(&self.min);
+        let rest = obstacles
+            .into_iter()
+            .filter(|o| axis.of(&o.max) < bottom && self.shadows(o, axis))
+            .map(|o| axis.of(&o.max) + Scalar::one())
+            .fold(floor, |rest, top| rest.max(top));
+        self.translate(axis.unit::<Scalar>() * (rest - bottom))
+    }
+
+    pub fn cells(&self) -> impl Iterator<Item = Pos3d<Scalar>> + '_ {
+        let range = |lo: Scalar, hi: Scalar| {
+            std::iter::successors(Some(lo), move |v| Some(*v + Scalar::one()))
+                .take_while(move |v| *v <= hi)
+        };
+        range(self.min.z, self.max.z).flat_map(move |z| {
+            range(self.min.y, self.max.y)
+                .flat_map(move |y| range(self.min.x, self.max.x).map(move |x| Pos3d::new(x, y, z)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brick(a: (i64, i64, i64), b: (i64, i64, i64)) -> Aabb3d<i64> {
+        Aabb3d::from_corners(Pos3d::new(a.0, a.1, a.2), Pos3d::new(b.0, b.1, b.2))
+    }
+
+    #[test]
+    fn test_overlap() {
+        let a = brick((0, 0, 0), (3, 3, 3));
+        let b = brick((2, 2, 2), (5, 5, 5));
+        assert_eq!(a.volume(), 64);
+        assert_eq!(a.overlap_volume(&b), 8);
+        assert_eq!(a.intersection(&b), Some(brick((2, 2, 2), (3, 3, 3))));
+        assert_eq!(a.overlap_volume(&brick((4, 0, 0), (5, 1, 1))), 0);
+        assert_eq!(a.cells().count(), 64);
+    }
+
+    #[test]
+    fn test_drop_along() {
+        let a = brick((1, 0, 1), (1, 2, 1));
+        let b = brick((0, 0, 2), (2, 0, 2));
+        let c = brick((0, 2, 5), (2, 2, 5));
+        let d = brick((5, 5, 9), (5, 5, 9));
+
+        let b = b.drop_along(Axis3d::Z, 1, [&a]);
+        assert_eq!(b, brick((0, 0, 2), (2, 0, 2)));
+        let c = c.drop_along(Axis3d::Z, 1, [&a, &b]);
+        assert_eq!(c, brick((0, 2, 2), (2, 2, 2)));
+        let d = d.drop_along(Axis3d::Z, 1, [&a, &b, &c]);
+        assert_eq!(d.min.z, 1);
+    }
+}
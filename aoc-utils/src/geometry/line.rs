@@ -0,0 +1,101 @@
+use std::fmt::Display;
+
+use num_traits::{PrimInt, Signed};
+
+use crate::{pos2d::Pos2d, vec2d::Vec2d};
+
+// A parametric line: pos + vel * t
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Line2d<Scalar: PrimInt + Signed> {
+    pub pos: Pos2d<Scalar>,
+    pub vel: Vec2d<Scalar>,
+}
+
+impl<Scalar: PrimInt + Signed + Display> Display for Line2d<Scalar> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} @ {}", self.pos, self.vel)
+    }
+}
+
+impl<Scalar: PrimInt + Signed> Line2d<Scalar> {
+    pub fn new(pos: Pos2d<Scalar>, vel: Vec2d<Scalar>) -> Line2d<Scalar> {
+        Line2d { pos, vel }
+    }
+
+    pub fn at(&self, t: Scalar) -> Pos2d<Scalar> {
+        self.pos + self.vel * t
+    }
+
+    pub fn at_f64(&self, t: f64) -> Option<Pos2d<f64>> {
+        Some(Pos2d::new(
+            self.pos.x.to_f64()? + self.vel.x.to_f64()? * t,
+            self.pos.y.to_f64()? + self.vel.y.to_f64()? * t,
+        ))
+    }
+
+    pub fn is_parallel(&self, other: &Self) -> bool {
+        (self.vel.x * other.vel.y - self.vel.y * other.vel.x).is_zero()
+    }
+
+    // The exact parameters (t, u, denominator) where the two lines meet:
+    // self.at(t / den) == other.at(u / den). The denominator is positive.
+    // None for parallel (or coincident) lines.
+    pub fn intersection_params(&self, other: &Self) -> Option<(Scalar, Scalar, Scalar)> {
+        let den = self.vel.x * other.vel.y - self.vel.y * other.vel.x;
+        if den.is_zero() {
+            return None;
+        }
+        let qp = (other.pos.x - self.pos.x, other.pos.y - self.pos.y);
+        let t = qp.0 * other.vel.y - qp.1 * other.vel.x;
+        let u = qp.0 * self.vel.y - qp.1 * self.vel.x;
+        if den < Scalar::zero() {
+            Some((-t, -u, -den))
+        } else {
+            Some((t, u, den))
+        }
+    }
+
+    // The times at which each line reaches the crossing point.
+    pub fn intersection_times(&self, other: &Self) -> Option<(f64, f64)> {
+        let (t, u, den) = self.intersection_params(other)?;
+        let den = den.to_f64()?;
+        Some((t.to_f64()? / den, u.to_f64()? / den))
+    }
+
+    pub fn intersection(&self, other: &Self) -> Option<Pos2d<f64>> {
+        let (t, _) = self.intersection_times(other)?;
+        self.at_f64(t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+
+    use super::*;
+
+    fn hail(px: i64, py: i64, vx: i64, vy: i64) -> Line2d<i64> {
+        Line2d::new(Pos2d::new(px, py), Vec2d::new(vx, vy))
+    }
+
+    #[test]
+    fn test_intersection() {
+        let a = hail(19, 13, -2, 1);
+        let b = hail(18, 19, -1, -1);
+        let (t, u) = a.intersection_times(&b).unwrap();
+        assert!(t > 0.0 && u > 0.0);
+        assert_abs_diff_eq!(
+            a.intersection(&b).unwrap(),
+            Pos2d::new(14.333, 15.333),
+            epsilon = 0.001
+        );
+
+        let c = hail(20, 25, -2, -2);
+        assert!(b.is_parallel(&c));
+        assert_eq!(b.intersection(&c), None);
+
+        let d = hail(20, 19, 1, -5);
+        let (t, _) = a.intersection_times(&d).unwrap();
+        assert!(t < 0.0);
+    }
+}
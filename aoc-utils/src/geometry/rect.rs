@@ -0,0 +1,193 @@
+use std::fmt::Display;
+
+use num_traits::{PrimInt, Signed};
+
+use super::segment::{cross, Segment2d};
+use crate::pos2d::Pos2d;
+
+// An axis aligned rectangle covering [min, max] (inclusive on both ends).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Rect2d<Scalar: PrimInt + Signed> {
+    pub min: Pos2d<Scalar>,
+    pub max: Pos2d<Scalar>,
+}
+
+impl<Scalar: PrimInt + Signed + Display> Display for Rect2d<Scalar> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}..{}]", self.min, self.max)
+    }
+}
+
+impl<Scalar: PrimInt + Signed> Rect2d<Scalar> {
+    pub fn new(min: Pos2d<Scalar>, max: Pos2d<Scalar>) -> Rect2d<Scalar> {
+        Rect2d { min, max }
+    }
+
+    pub fn from_corners(a: Pos2d<Scalar>, b: Pos2d<Scalar>) -> Rect2d<Scalar> {
+        Rect2d {
+            min: Pos2d::new(a.x.min(b.x), a.y.min(b.y)),
+            max: Pos2d::new(a.x.max(b.x), a.y.max(b.y)),
+        }
+    }
+
+    pub fn width(&self) -> Scalar {
+        self.max.x - self.min.x
+    }
+
+    pub fn height(&self) -> Scalar {
+        self.max.y - self.min.y
+    }
+
+    pub fn area(&self) -> Scalar {
+        self.width() * self.height()
+    }
+
+    // The number of grid cells covered, treating min and max as cells.
+    pub fn cells(&self) -> Scalar {
+        (self.width() + Scalar::one()) * (self.height() + Scalar::one())
+    }
+
+    // Clockwise from min.
+    pub fn corners(&self) -> [Pos2d<Scalar>; 4] {
+        [
+            self.min,
+            Pos2d::new(self.max.x, self.min.y),
+            self.max,
+            Pos2d::new(self.min.x, self.max.y),
+        ]
+    }
+
+    pub fn edges(&self) -> [Segment2d<Scalar>; 4] {
+        let c = self.corners();
+        [
+            Segment2d::new(c[0], c[1]),
+            Segment2d::new(c[1], c[2]),
+            Segment2d::new(c[2], c[3]),
+            Segment2d::new(c[3], c[0]),
+        ]
+    }
+
+    pub fn contains(&self, p: &Pos2d<Scalar>) -> bool {
+        p.x >= self.min.x && p.x <= self.max.x && p.y >= self.min.y && p.y <= self.max.y
+    }
+
+    pub fn contains_rect(&self, other: &Self) -> bool {
+        self.contains(&other.min) && self.contains(&other.max)
+    }
+
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.min.x <= other.max.x
+            && other.min.x <= self.max.x
+            && self.min.y <= other.max.y
+            && other.min.y <= self.max.y
+    }
+
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        if self.intersects(other) {
+            Some(Rect2d::new(
+                Pos2d::new(self.min.x.max(other.min.x), self.min.y.max(other.min.y)),
+                Pos2d::new(self.max.x.min(other.max.x), self.max.y.min(other.max.y)),
+            ))
+        } else {
+            None
+        }
+    }
+
+    // True if any part of the segment lies strictly inside the rectangle.
+    // Uses the separating axis test against the open rectangle.
+    pub fn interior_intersects(&self, seg: &Segment2d<Scalar>) -> bool {
+        let (smin, smax) = (seg.min(), seg.max());
+        if smax.x <= self.min.x
+            || smin.x >= self.max.x
+            || smax.y <= self.min.y
+            || smin.y >= self.max.y
+        {
+            return false;
+        }
+        let sides = self.corners().map(|c| cross(&seg.a, &seg.b, &c).signum());
+        let all_one_side = sides.iter().all(|s| *s >= Scalar::zero())
+            || sides.iter().all(|s| *s <= Scalar::zero());
+        !all_one_side
+    }
+}
+
+// The area of the union of rectangles, using a sweep line along x.
+// Rectangles are treated as continuous regions, so a rectangle with zero width
+// contributes nothing. Use cell coordinates with max + 1 to count grid cells.
+pub fn union_area<Scalar: PrimInt + Signed>(rects: &[Rect2d<Scalar>]) -> Scalar {
+    let mut xs = rects
+        .iter()
+        .flat_map(|r| [r.min.x, r.max.x])
+        .collect::<Vec<_>>();
+    xs.sort();
+    xs.dedup();
+
+    xs.windows(2).fold(Scalar::zero(), |area, w| {
+        let (x0, x1) = (w[0], w[1]);
+        let mut spans = rects
+            .iter()
+            .filter(|r| r.min.x <= x0 && r.max.x >= x1)
+            .map(|r| (r.min.y, r.max.y))
+            .collect::<Vec<_>>();
+        spans.sort();
+
+        let mut covered = Scalar::zero();
+        let mut current: Option<(Scalar, Scalar)> = None;
+        for (lo, hi) in spans {
+            current = match current {
+                Some((clo, chi)) if lo <= chi => Some((clo, chi.max(hi))),
+                Some((clo, chi)) => {
+                    covered = covered + chi - clo;
+                    Some((lo, hi))
+                }
+                None => Some((lo, hi)),
+            };
+        }
+        if let Some((clo, chi)) = current {
+            covered = covered + chi - clo;
+        }
+        area + covered * (x1 - x0)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(ax: i64, ay: i64, bx: i64, by: i64) -> Rect2d<i64> {
+        Rect2d::from_corners(Pos2d::new(ax, ay), Pos2d::new(bx, by))
+    }
+
+    #[test]
+    fn test_rect() {
+        let r = rect(9, 5, 2, 3);
+        assert_eq!(r.min, Pos2d::new(2, 3));
+        assert_eq!(r.area(), 14);
+        assert_eq!(r.cells(), 24);
+        assert!(r.contains(&Pos2d::new(9, 3)));
+        assert!(!r.contains(&Pos2d::new(10, 3)));
+        assert_eq!(r.intersection(&rect(5, 0, 12, 4)), Some(rect(5, 3, 9, 4)));
+        assert_eq!(r.intersection(&rect(10, 0, 12, 4)), None);
+    }
+
+    #[test]
+    fn test_interior_intersects() {
+        let r = rect(0, 0, 4, 4);
+        let seg = |ax, ay, bx, by| Segment2d::new(Pos2d::new(ax, ay), Pos2d::new(bx, by));
+        assert!(r.interior_intersects(&seg(2, -1, 2, 5)));
+        assert!(r.interior_intersects(&seg(-1, -1, 5, 5)));
+        assert!(!r.interior_intersects(&seg(0, -1, 0, 5)));
+        assert!(!r.interior_intersects(&seg(-1, 4, 1, 6)));
+        assert!(!r.interior_intersects(&seg(3, -2, 6, 1)));
+        assert!(!r.interior_intersects(&seg(3, -1, 6, 2)));
+        assert!(r.interior_intersects(&seg(2, -1, 6, 3)));
+    }
+
+    #[test]
+    fn test_union_area() {
+        assert_eq!(union_area(&[rect(0, 0, 4, 4), rect(2, 2, 6, 6)]), 28);
+        assert_eq!(union_area(&[rect(0, 0, 4, 4), rect(1, 1, 2, 2)]), 16);
+        assert_eq!(union_area(&[rect(0, 0, 1, 1), rect(3, 3, 4, 4)]), 2);
+        assert_eq!(union_area::<i64>(&[]), 0);
+    }
+}
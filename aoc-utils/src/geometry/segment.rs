@@ -0,0 +1,169 @@
+use std::fmt::Display;
+
+use num_traits::{PrimInt, Signed};
+
+use crate::pos2d::Pos2d;
+
+// Twice the signed area of the triangle (o, a, b).
+// Positive if b is clockwise of a around o (in y-down grid coordinates).
+pub fn cross<Scalar: PrimInt + Signed>(
+    o: &Pos2d<Scalar>,
+    a: &Pos2d<Scalar>,
+    b: &Pos2d<Scalar>,
+) -> Scalar {
+    (a.x - o.x) * (b.y - o.y) - (a.y - o.y) * (b.x - o.x)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Segment2d<Scalar: PrimInt + Signed> {
+    pub a: Pos2d<Scalar>,
+    pub b: Pos2d<Scalar>,
+}
+
+impl<Scalar: PrimInt + Signed + Display> Display for Segment2d<Scalar> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.a, self.b)
+    }
+}
+
+impl<Scalar: PrimInt + Signed> Segment2d<Scalar> {
+    pub fn new(a: Pos2d<Scalar>, b: Pos2d<Scalar>) -> Segment2d<Scalar> {
+        Segment2d { a, b }
+    }
+
+    pub fn is_horizontal(&self) -> bool {
+        self.a.y == self.b.y
+    }
+
+    pub fn is_vertical(&self) -> bool {
+        self.a.x == self.b.x
+    }
+
+    pub fn min(&self) -> Pos2d<Scalar> {
+        Pos2d::new(self.a.x.min(self.b.x), self.a.y.min(self.b.y))
+    }
+
+    pub fn max(&self) -> Pos2d<Scalar> {
+        Pos2d::new(self.a.x.max(self.b.x), self.a.y.max(self.b.y))
+    }
+
+    fn in_bounds(&self, p: &Pos2d<Scalar>) -> bool {
+        let (min, max) = (self.min(), self.max());
+        p.x >= min.x && p.x <= max.x && p.y >= min.y && p.y <= max.y
+    }
+
+    pub fn contains(&self, p: &Pos2d<Scalar>) -> bool {
+        cross(&self.a, &self.b, p).is_zero() && self.in_bounds(p)
+    }
+
+    // True if the segments share at least one point (touching counts).
+    pub fn intersects(&self, other: &Self) -> bool {
+        let d1 = cross(&other.a, &other.b, &self.a).signum();
+        let d2 = cross(&other.a, &other.b, &self.b).signum();
+        let d3 = cross(&self.a, &self.b, &other.a).signum();
+        let d4 = cross(&self.a, &self.b, &other.b).signum();
+
+        if d1 * d2 < Scalar::zero() && d3 * d4 < Scalar::zero() {
+            return true;
+        }
+
+        other.contains(&self.a)
+            || other.contains(&self.b)
+            || self.contains(&other.a)
+            || self.contains(&other.b)
+    }
+
+    // True if the segments cross at a single point interior to both.
+    pub fn crosses(&self, other: &Self) -> bool {
+        let d1 = cross(&other.a, &other.b, &self.a).signum();
+        let d2 = cross(&other.a, &other.b, &self.b).signum();
+        let d3 = cross(&self.a, &self.b, &other.a).signum();
+        let d4 = cross(&self.a, &self.b, &other.b).signum();
+        d1 * d2 < Scalar::zero() && d3 * d4 < Scalar::zero()
+    }
+
+    // The exact intersection parameters as (t, u, denominator), such that
+    // the crossing is at a + (b - a) * t / den == other.a + (other.b - other.a) * u / den.
+    // The denominator is always positive. None if the segments are parallel or
+    // don't meet.
+    pub fn intersection_params(&self, other: &Self) -> Option<(Scalar, Scalar, Scalar)> {
+        let (r, s) = (
+            (self.b.x - self.a.x, self.b.y - self.a.y),
+            (other.b.x - other.a.x, other.b.y - other.a.y),
+        );
+        let qp = (other.a.x - self.a.x, other.a.y - self.a.y);
+        let den = r.0 * s.1 - r.1 * s.0;
+        if den.is_zero() {
+            return None;
+        }
+        let t = qp.0 * s.1 - qp.1 * s.0;
+        let u = qp.0 * r.1 - qp.1 * r.0;
+        let (t, u, den) = if den < Scalar::zero() {
+            (-t, -u, -den)
+        } else {
+            (t, u, den)
+        };
+        let valid = |v: Scalar| v >= Scalar::zero() && v <= den;
+        if valid(t) && valid(u) {
+            Some((t, u, den))
+        } else {
+            None
+        }
+    }
+
+    // The crossing point, if it lies exactly on the integer lattice.
+    pub fn lattice_intersection(&self, other: &Self) -> Option<Pos2d<Scalar>> {
+        let (t, _, den) = self.intersection_params(other)?;
+        let nx = (self.b.x - self.a.x) * t;
+        let ny = (self.b.y - self.a.y) * t;
+        if (nx % den).is_zero() && (ny % den).is_zero() {
+            Some(Pos2d::new(self.a.x + nx / den, self.a.y + ny / den))
+        } else {
+            None
+        }
+    }
+
+    pub fn intersection(&self, other: &Self) -> Option<Pos2d<f64>> {
+        let (t, _, den) = self.intersection_params(other)?;
+        let t = t.to_f64()? / den.to_f64()?;
+        let (ax, ay) = (self.a.x.to_f64()?, self.a.y.to_f64()?);
+        let (bx, by) = (self.b.x.to_f64()?, self.b.y.to_f64()?);
+        Some(Pos2d::new(ax + (bx - ax) * t, ay + (by - ay) * t))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+
+    use super::*;
+
+    fn seg(ax: i64, ay: i64, bx: i64, by: i64) -> Segment2d<i64> {
+        Segment2d::new(Pos2d::new(ax, ay), Pos2d::new(bx, by))
+    }
+
+    #[test]
+    fn test_intersects() {
+        assert!(seg(0, 0, 4, 4).intersects(&seg(0, 4, 4, 0)));
+        assert!(seg(0, 0, 4, 4).crosses(&seg(0, 4, 4, 0)));
+        assert!(seg(0, 0, 4, 0).intersects(&seg(4, 0, 4, 4)));
+        assert!(!seg(0, 0, 4, 0).crosses(&seg(4, 0, 4, 4)));
+        assert!(seg(0, 0, 4, 0).intersects(&seg(2, 0, 6, 0)));
+        assert!(!seg(0, 0, 4, 0).intersects(&seg(5, 0, 6, 0)));
+        assert!(!seg(0, 0, 4, 0).intersects(&seg(0, 1, 4, 1)));
+    }
+
+    #[test]
+    fn test_intersection() {
+        assert_eq!(
+            seg(0, 0, 4, 4).lattice_intersection(&seg(0, 4, 4, 0)),
+            Some(Pos2d::new(2, 2))
+        );
+        assert_eq!(seg(0, 0, 1, 1).lattice_intersection(&seg(0, 1, 1, 0)), None);
+        assert_abs_diff_eq!(
+            seg(0, 0, 1, 1).intersection(&seg(0, 1, 1, 0)).unwrap(),
+            Pos2d::new(0.5, 0.5)
+        );
+        assert_eq!(seg(0, 0, 1, 1).intersection(&seg(2, 0, 3, -1)), None);
+    }
+}
@@ -0,0 +1,122 @@
+use std::fmt::Display;
+
+use num_traits::{PrimInt, Signed};
+
+use crate::pos2d::Pos2d;
+
+// All points within a manhattan distance of a centre (a diamond).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TaxiBall<Scalar: PrimInt + Signed> {
+    pub centre: Pos2d<Scalar>,
+    pub radius: Scalar,
+}
+
+impl<Scalar: PrimInt + Signed + Display> Display for TaxiBall<Scalar> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<{} r={}>", self.centre, self.radius)
+    }
+}
+
+impl<Scalar: PrimInt + Signed> TaxiBall<Scalar> {
+    pub fn new(centre: Pos2d<Scalar>, radius: Scalar) -> TaxiBall<Scalar> {
+        TaxiBall { centre, radius }
+    }
+
+    // The ball centred on `centre` that just reaches `edge`.
+    pub fn reaching(centre: Pos2d<Scalar>, edge: Pos2d<Scalar>) -> TaxiBall<Scalar> {
+        TaxiBall::new(centre, centre.taxi_distance(&edge))
+    }
+
+    pub fn contains(&self, p: &Pos2d<Scalar>) -> bool {
+        self.centre.taxi_distance(p) <= self.radius
+    }
+
+    // The inclusive x range covered on row y.
+    pub fn row_span(&self, y: Scalar) -> Option<(Scalar, Scalar)> {
+        let half = self.radius - (y - self.centre.y).abs();
+        if half < Scalar::zero() {
+            None
+        } else {
+            Some((self.centre.x - half, self.centre.x + half))
+        }
+    }
+
+    // All points at exactly distance r from the centre, clockwise from the top.
+    pub fn ring(&self, r: Scalar) -> impl Iterator<Item = Pos2d<Scalar>> {
+        let c = self.centre;
+        let r = r.max(Scalar::zero());
+        let zero = r.is_zero();
+        let end = r.max(Scalar::one());
+        std::iter::successors(Some(Scalar::zero()), |i| Some(*i + Scalar::one()))
+            .take_while(move |i| *i < end)
+            .flat_map(move |i| {
+                let j = r - i;
+                let points = [
+                    Pos2d::new(c.x + i, c.y - j),
+                    Pos2d::new(c.x + j, c.y + i),
+                    Pos2d::new(c.x - i, c.y + j),
+                    Pos2d::new(c.x - j, c.y - i),
+                ];
+                points.into_iter().take(if zero { 1 } else { 4 })
+            })
+    }
+
+    // The points just outside the ball.
+    pub fn perimeter(&self) -> impl Iterator<Item = Pos2d<Scalar>> {
+        self.ring(self.radius + Scalar::one())
+    }
+}
+
+// The merged, sorted, inclusive x spans covered by the balls on row y.
+pub fn row_coverage<Scalar: PrimInt + Signed>(
+    balls: &[TaxiBall<Scalar>],
+    y: Scalar,
+) -> Vec<(Scalar, Scalar)> {
+    let mut spans = balls
+        .iter()
+        .filter_map(|b| b.row_span(y))
+        .collect::<Vec<_>>();
+    spans.sort();
+
+    let mut merged: Vec<(Scalar, Scalar)> = Vec::new();
+    for (lo, hi) in spans {
+        match merged.last_mut() {
+            Some(last) if lo <= last.1 + Scalar::one() => last.1 = last.1.max(hi),
+            _ => merged.push((lo, hi)),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn test_taxi_ball() {
+        let ball = TaxiBall::reaching(Pos2d::<i64>::new(8, 7), Pos2d::new(2, 10));
+        assert_eq!(ball.radius, 9);
+        assert!(ball.contains(&Pos2d::new(8, -2)));
+        assert!(!ball.contains(&Pos2d::new(9, -2)));
+        assert_eq!(ball.row_span(10), Some((2, 14)));
+        assert_eq!(ball.row_span(17), None);
+
+        let ring = ball.perimeter().collect::<HashSet<_>>();
+        assert_eq!(ring.len(), 40);
+        assert!(ring.iter().all(|p| ball.centre.taxi_distance(p) == 10));
+        assert_eq!(ball.ring(0).collect::<Vec<_>>(), vec![ball.centre]);
+    }
+
+    #[test]
+    fn test_row_coverage() {
+        let balls = [
+            TaxiBall::new(Pos2d::<i64>::new(0, 0), 2),
+            TaxiBall::new(Pos2d::new(3, 1), 1),
+            TaxiBall::new(Pos2d::new(10, 0), 1),
+        ];
+        assert_eq!(row_coverage(&balls, 1), vec![(-1, 4), (10, 10)]);
+        assert_eq!(row_coverage(&balls, 3), vec![]);
+    }
+}
@@ -0,0 +1,407 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::{geometry::aabb3d::Aabb3d, grud::Error, pos3d::Pos3d, vec3d::Vec3d};
+
+pub type Grid3dPos = Pos3d<i64>;
+pub type Grid3dVec = Vec3d<i64>;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Neighbourhood {
+    // The 6 cells sharing a face
+    Faces,
+    // The 26 cells sharing a face, edge or corner
+    All,
+}
+
+impl Neighbourhood {
+    pub const FACES: [Grid3dVec; 6] = [
+        Vec3d::new(1, 0, 0),
+        Vec3d::new(-1, 0, 0),
+        Vec3d::new(0, 1, 0),
+        Vec3d::new(0, -1, 0),
+        Vec3d::new(0, 0, 1),
+        Vec3d::new(0, 0, -1),
+    ];
+
+    pub fn offsets(&self) -> Vec<Grid3dVec> {
+        match self {
+            Neighbourhood::Faces => Self::FACES.to_vec(),
+            Neighbourhood::All => (-1..=1)
+                .flat_map(|z| {
+                    (-1..=1).flat_map(move |y| (-1..=1).map(move |x| Vec3d::new(x, y, z)))
+                })
+                .filter(|v| *v != Vec3d::new(0, 0, 0))
+                .collect(),
+        }
+    }
+
+    pub fn around(&self, pos: Grid3dPos) -> impl Iterator<Item = Grid3dPos> {
+        self.offsets().into_iter().map(move |v| pos + v)
+    }
+}
+
+fn flood<Pred>(start: Grid3dPos, nbh: Neighbourhood, passable: Pred) -> HashSet<Grid3dPos>
+where
+    Pred: Fn(&Grid3dPos) -> bool,
+{
+    let mut seen = HashSet::new();
+    if !passable(&start) {
+        return seen;
+    }
+    let offsets = nbh.offsets();
+    let mut q = VecDeque::from([start]);
+    seen.insert(start);
+    while let Some(pos) = q.pop_front() {
+        for &v in &offsets {
+            let next = pos + v;
+            if !seen.contains(&next) && passable(&next) {
+                seen.insert(next);
+                q.push_back(next);
+            }
+        }
+    }
+    seen
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid3d<Item: Copy + Eq> {
+    width: usize,
+    height: usize,
+    depth: usize,
+    g: Vec<Item>,
+}
+
+impl<Item: Copy + Eq> Grid3d<Item> {
+    pub fn new(item: Item, width: usize, height: usize, depth: usize) -> Grid3d<Item> {
+        Grid3d {
+            width,
+            height,
+            depth,
+            g: vec![item; width * height * depth],
+        }
+    }
+
+    pub fn create(
+        width: usize,
+        height: usize,
+        depth: usize,
+        g: Vec<Item>,
+    ) -> Result<Grid3d<Item>, Error> {
+        if g.len() == width * height * depth {
+            Ok(Grid3d {
+                width,
+                height,
+                depth,
+                g,
+            })
+        } else {
+            Err(Error::BadSize)
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn bounds(&self) -> Aabb3d<i64> {
+        Aabb3d::new(
+            Pos3d::new(0, 0, 0),
+            Pos3d::new(
+                self.width as i64 - 1,
+                self.height as i64 - 1,
+                self.depth as i64 - 1,
+            ),
+        )
+    }
+
+    pub fn is_valid(&self, pos: &Grid3dPos) -> bool {
+        pos.x >= 0
+            && pos.x < self.width as i64
+            && pos.y >= 0
+            && pos.y < self.height as i64
+            && pos.z >= 0
+            && pos.z < self.depth as i64
+    }
+
+    fn i_from(&self, pos: &Grid3dPos) -> usize {
+        pos.x as usize + (pos.y as usize + pos.z as usize * self.height) * self.width
+    }
+
+    pub fn pos_from(&self, i: usize) -> Grid3dPos {
+        let plane = self.width * self.height;
+        Pos3d::new(
+            (i % self.width) as i64,
+            ((i % plane) / self.width) as i64,
+            (i / plane) as i64,
+        )
+    }
+
+    pub fn at(&self, pos: &Grid3dPos) -> Option<Item> {
+        if self.is_valid(pos) {
+            Some(self.g[self.i_from(pos)])
+        } else {
+            None
+        }
+    }
+
+    pub fn set(&mut self, pos: &Grid3dPos, val: Item) -> Option<Item> {
+        if self.is_valid(pos) {
+            let i = self.i_from(pos);
+            Some(std::mem::replace(&mut self.g[i], val))
+        } else {
+            None
+        }
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Item> {
+        self.g.iter()
+    }
+
+    pub fn iter_pos(&self) -> impl Iterator<Item = Grid3dPos> + '_ {
+        (0..self.g.len()).map(|i| self.pos_from(i))
+    }
+
+    pub fn iter_pair(&self) -> impl Iterator<Item = (Grid3dPos, Item)> + '_ {
+        (0..self.g.len()).map(|i| (self.pos_from(i), self.g[i]))
+    }
+
+    pub fn neighbours(
+        &self,
+        pos: Grid3dPos,
+        nbh: Neighbourhood,
+    ) -> impl Iterator<Item = Grid3dPos> + '_ {
+        nbh.around(pos).filter(|p| self.is_valid(p))
+    }
+
+    // All cells reachable from start through cells matching the predicate.
+    pub fn flood_fill<Pred>(
+        &self,
+        start: Grid3dPos,
+        nbh: Neighbourhood,
+        passable: Pred,
+    ) -> HashSet<Grid3dPos>
+    where
+        Pred: Fn(Item) -> bool,
+    {
+        flood(start, nbh, |p| self.at(p).is_some_and(&passable))
+    }
+
+    // The number of faces of solid cells that touch a non-solid cell or the
+    // edge of the grid.
+    pub fn exposed_faces<Pred>(&self, solid: Pred) -> usize
+    where
+        Pred: Fn(Item) -> bool,
+    {
+        self.iter_pair()
+            .filter(|(_, item)| solid(*item))
+            .map(|(pos, _)| {
+                Neighbourhood::Faces
+                    .around(pos)
+                    .filter(|n| !self.at(n).is_some_and(&solid))
+                    .count()
+            })
+            .sum()
+    }
+
+    // As exposed_faces, but ignoring faces touching pockets enclosed by solid cells.
+    pub fn exterior_faces<Pred>(&self, solid: Pred) -> usize
+    where
+        Pred: Fn(Item) -> bool,
+    {
+        let bounds = self.bounds();
+        let limits = Aabb3d::new(
+            bounds.min - Vec3d::new(1, 1, 1),
+            bounds.max + Vec3d::new(1, 1, 1),
+        );
+        let outside = flood(limits.min, Neighbourhood::Faces, |p| {
+            limits.contains(p) && !self.at(p).is_some_and(&solid)
+        });
+        self.iter_pair()
+            .filter(|(_, item)| solid(*item))
+            .map(|(pos, _)| {
+                Neighbourhood::Faces
+                    .around(pos)
+                    .filter(|n| outside.contains(n))
+                    .count()
+            })
+            .sum()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SparseGrid3d<Item = ()> {
+    cells: HashMap<Grid3dPos, Item>,
+}
+
+impl<Item> SparseGrid3d<Item> {
+    pub fn new() -> SparseGrid3d<Item> {
+        SparseGrid3d {
+            cells: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn insert(&mut self, pos: Grid3dPos, item: Item) -> Option<Item> {
+        self.cells.insert(pos, item)
+    }
+
+    pub fn remove(&mut self, pos: &Grid3dPos) -> Option<Item> {
+        self.cells.remove(pos)
+    }
+
+    pub fn get(&self, pos: &Grid3dPos) -> Option<&Item> {
+        self.cells.get(pos)
+    }
+
+    pub fn contains(&self, pos: &Grid3dPos) -> bool {
+        self.cells.contains_key(pos)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Grid3dPos, &Item)> {
+        self.cells.iter()
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = &Grid3dPos> {
+        self.cells.keys()
+    }
+
+    pub fn bounds(&self) -> Option<Aabb3d<i64>> {
+        Aabb3d::from_points(self.cells.keys())
+    }
+
+    // The occupied cells around pos.
+    pub fn neighbours(
+        &self,
+        pos: Grid3dPos,
+        nbh: Neighbourhood,
+    ) -> impl Iterator<Item = Grid3dPos> + '_ {
+        nbh.around(pos).filter(|p| self.contains(p))
+    }
+
+    // All empty cells reachable from start without leaving the limits.
+    pub fn flood_fill(
+        &self,
+        start: Grid3dPos,
+        nbh: Neighbourhood,
+        limits: &Aabb3d<i64>,
+    ) -> HashSet<Grid3dPos> {
+        flood(start, nbh, |p| limits.contains(p) && !self.contains(p))
+    }
+
+    // The number of cell faces not shared with another occupied cell.
+    pub fn surface_area(&self) -> usize {
+        self.cells
+            .keys()
+            .map(|&pos| {
+                Neighbourhood::Faces
+                    .around(pos)
+                    .filter(|n| !self.contains(n))
+                    .count()
+            })
+            .sum()
+    }
+
+    // As surface_area, but ignoring faces touching enclosed air pockets.
+    pub fn exterior_surface_area(&self) -> usize {
+        let Some(bounds) = self.bounds() else {
+            return 0;
+        };
+        let limits = Aabb3d::new(
+            bounds.min - Vec3d::new(1, 1, 1),
+            bounds.max + Vec3d::new(1, 1, 1),
+        );
+        let outside = self.flood_fill(limits.min, Neighbourhood::Faces, &limits);
+        self.cells
+            .keys()
+            .map(|&pos| {
+                Neighbourhood::Faces
+                    .around(pos)
+                    .filter(|n| outside.contains(n))
+                    .count()
+            })
+            .sum()
+    }
+}
+
+impl<Item> FromIterator<(Grid3dPos, Item)> for SparseGrid3d<Item> {
+    fn from_iter<T: IntoIterator<Item = (Grid3dPos, Item)>>(iter: T) -> Self {
+        SparseGrid3d {
+            cells: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl FromIterator<Grid3dPos> for SparseGrid3d<()> {
+    fn from_iter<T: IntoIterator<Item = Grid3dPos>>(iter: T) -> Self {
+        iter.into_iter().map(|p| (p, ())).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DROPLET: &str =
+        "2,2,2\n1,2,2\n3,2,2\n2,1,2\n2,3,2\n2,2,1\n2,2,3\n2,2,4\n2,2,6\n1,2,5\n3,2,5\n2,1,5\n2,3,5";
+
+    fn droplet() -> SparseGrid3d {
+        DROPLET
+            .lines()
+            .map(|line| {
+                let v = line
+                    .split(',')
+                    .map(|n| n.parse::<i64>().unwrap())
+                    .collect::<Vec<_>>();
+                Pos3d::new(v[0], v[1], v[2])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_neighbourhood() {
+        assert_eq!(Neighbourhood::Faces.offsets().len(), 6);
+        assert_eq!(Neighbourhood::All.offsets().len(), 26);
+    }
+
+    #[test]
+    fn test_sparse_surface() {
+        let droplet = droplet();
+        assert_eq!(droplet.len(), 13);
+        assert_eq!(droplet.surface_area(), 64);
+        assert_eq!(droplet.exterior_surface_area(), 58);
+    }
+
+    #[test]
+    fn test_grid_surface() {
+        let mut grid = Grid3d::new(false, 4, 4, 7);
+        droplet().positions().for_each(|p| {
+            grid.set(p, true);
+        });
+        assert_eq!(
+            grid.pos_from(grid.i_from(&Pos3d::new(1, 2, 3))),
+            Pos3d::new(1, 2, 3)
+        );
+        assert_eq!(grid.exposed_faces(|solid| solid), 64);
+        assert_eq!(grid.exterior_faces(|solid| solid), 58);
+        assert_eq!(
+            grid.flood_fill(Pos3d::new(0, 0, 0), Neighbourhood::Faces, |solid| !solid)
+                .len(),
+            4 * 4 * 7 - 13 - 1
+        );
+    }
+}
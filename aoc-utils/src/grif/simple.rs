@@ -106,7 +106,7 @@ impl<'a> NodeIdFromStr<'a> for &'a str {
     }
 }
 
-impl<'a, NodeId: Copy + Ord> SimpleGraphBuilder<NodeId> {
+impl<NodeId: Copy + Ord> SimpleGraphBuilder<NodeId> {
     pub fn new(name: &str) -> SimpleGraphBuilder<NodeId> {
         SimpleGraphBuilder {
             graph: SimpleGraph {
@@ -250,21 +250,21 @@ mod test {
         assert_eq!(graph.edges().count(), 7);
 
         assert_eq!(graph.node_edges(1).count(), 2);
-        assert_eq!(graph.nodes_are_adjacent(1, 2), true);
-        assert_eq!(graph.nodes_are_adjacent(1, 3), true);
-        assert_eq!(graph.nodes_are_adjacent(1, 4), false);
+        assert!(graph.nodes_are_adjacent(1, 2));
+        assert!(graph.nodes_are_adjacent(1, 3));
+        assert!(!graph.nodes_are_adjacent(1, 4));
         assert_eq!(graph.degree(1), 2);
 
         assert_eq!(graph.node_edges(2).count(), 2);
-        assert_eq!(graph.nodes_are_adjacent(2, 1), true);
-        assert_eq!(graph.nodes_are_adjacent(2, 3), true);
-        assert_eq!(graph.nodes_are_adjacent(2, 4), false);
+        assert!(graph.nodes_are_adjacent(2, 1));
+        assert!(graph.nodes_are_adjacent(2, 3));
+        assert!(!graph.nodes_are_adjacent(2, 4));
         assert_eq!(graph.degree(2), 2);
 
         assert_eq!(graph.node_edges(3).count(), 3);
-        assert_eq!(graph.nodes_are_adjacent(3, 1), true);
-        assert_eq!(graph.nodes_are_adjacent(3, 2), true);
-        assert_eq!(graph.nodes_are_adjacent(3, 4), true);
+        assert!(graph.nodes_are_adjacent(3, 1));
+        assert!(graph.nodes_are_adjacent(3, 2));
+        assert!(graph.nodes_are_adjacent(3, 4));
         assert_eq!(graph.degree(3), 3);
 
         println!("{graph}");
@@ -1,4 +1,6 @@
+pub mod compressed;
 pub mod grif;
+pub mod hex;
 
 use enum_iterator::Sequence;
 
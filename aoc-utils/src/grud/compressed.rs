@@ -0,0 +1,255 @@
+use std::collections::{HashSet, VecDeque};
+
+use enum_iterator::Sequence;
+
+use super::{Grid, GridPos};
+use crate::dir::Dir;
+
+// Maps real coordinates along one axis onto compressed cell indices.
+// Every interesting coordinate gets a cell of its own, and each gap between
+// them collapses into a single cell spanning the gap.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompressedAxis {
+    starts: Vec<i64>,
+    sizes: Vec<i64>,
+}
+
+impl CompressedAxis {
+    // Builds the axis with an extra cell of padding either side, so that a flood
+    // fill from cell 0 can reach around everything.
+    pub fn new(coords: impl IntoIterator<Item = i64>) -> CompressedAxis {
+        let mut coords = coords.into_iter().collect::<Vec<_>>();
+        coords.sort();
+        coords.dedup();
+
+        let mut starts = Vec::new();
+        let mut sizes = Vec::new();
+        if let Some(&first) = coords.first() {
+            starts.push(first - 1);
+            sizes.push(1);
+        }
+        for (i, &c) in coords.iter().enumerate() {
+            starts.push(c);
+            sizes.push(1);
+            let next = coords.get(i + 1).copied().unwrap_or(c + 2);
+            if next - c > 1 {
+                starts.push(c + 1);
+                sizes.push(next - c - 1);
+            }
+        }
+        CompressedAxis { starts, sizes }
+    }
+
+    pub fn len(&self) -> usize {
+        self.starts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.starts.is_empty()
+    }
+
+    // The compressed cell containing the real coordinate.
+    pub fn compress(&self, real: i64) -> Option<usize> {
+        let i = self.starts.partition_point(|&s| s <= real).checked_sub(1)?;
+        if real < self.starts[i] + self.sizes[i] {
+            Some(i)
+        } else {
+            None
+        }
+    }
+
+    // The first real coordinate covered by the cell.
+    pub fn real(&self, i: usize) -> i64 {
+        self.starts[i]
+    }
+
+    // The number of real coordinates covered by the cell.
+    pub fn size(&self, i: usize) -> i64 {
+        self.sizes[i]
+    }
+}
+
+// A Grid over compressed coordinates, where each cell stands in for a
+// rectangle of real cells.
+#[derive(Debug, Clone)]
+pub struct CompressedGrid<Item, D>
+where
+    Item: Copy + Eq,
+    D: Dir,
+{
+    grid: Grid<Item, D>,
+    xs: CompressedAxis,
+    ys: CompressedAxis,
+}
+
+impl<Item, D> CompressedGrid<Item, D>
+where
+    Item: Copy + Eq,
+    D: Dir + Sequence,
+{
+    pub fn new<'a>(points: impl IntoIterator<Item = &'a GridPos>, item: Item) -> Self {
+        let (xs, ys): (Vec<_>, Vec<_>) = points.into_iter().map(|p| (p.x, p.y)).unzip();
+        let xs = CompressedAxis::new(xs);
+        let ys = CompressedAxis::new(ys);
+        CompressedGrid {
+            grid: Grid::new(item, xs.len(), ys.len()),
+            xs,
+            ys,
+        }
+    }
+
+    pub fn grid(&self) -> &Grid<Item, D> {
+        &self.grid
+    }
+
+    pub fn grid_mut(&mut self) -> &mut Grid<Item, D> {
+        &mut self.grid
+    }
+
+    pub fn xs(&self) -> &CompressedAxis {
+        &self.xs
+    }
+
+    pub fn ys(&self) -> &CompressedAxis {
+        &self.ys
+    }
+
+    pub fn compress(&self, real: &GridPos) -> Option<GridPos> {
+        Some(GridPos::new(
+            self.xs.compress(real.x)? as i64,
+            self.ys.compress(real.y)? as i64,
+        ))
+    }
+
+    // The top left real position covered by the compressed cell.
+    pub fn real(&self, pos: &GridPos) -> GridPos {
+        GridPos::new(self.xs.real(pos.x as usize), self.ys.real(pos.y as usize))
+    }
+
+    // The real (width, height) covered by the compressed cell.
+    pub fn cell_size(&self, pos: &GridPos) -> (i64, i64) {
+        (self.xs.size(pos.x as usize), self.ys.size(pos.y as usize))
+    }
+
+    pub fn cell_area(&self, pos: &GridPos) -> i64 {
+        let (w, h) = self.cell_size(pos);
+        w * h
+    }
+
+    pub fn at(&self, real: &GridPos) -> Option<Item> {
+        self.grid.at(&self.compress(real)?)
+    }
+
+    // Sets every compressed cell touched by the real rectangle between a and b.
+    pub fn fill(&mut self, a: &GridPos, b: &GridPos, item: Item) -> Option<()> {
+        let (ca, cb) = (self.compress(a)?, self.compress(b)?);
+        for y in ca.y.min(cb.y)..=ca.y.max(cb.y) {
+            for x in ca.x.min(cb.x)..=ca.x.max(cb.x) {
+                self.grid.set(&GridPos::new(x, y), item);
+            }
+        }
+        Some(())
+    }
+
+    // All compressed cells reachable from start through cells matching the predicate.
+    pub fn flood_fill<Pred>(&self, start: GridPos, passable: Pred) -> HashSet<GridPos>
+    where
+        Pred: Fn(Item) -> bool,
+    {
+        let mut seen = HashSet::new();
+        if !self.grid.at(&start).is_some_and(&passable) {
+            return seen;
+        }
+        seen.insert(start);
+        let mut q = VecDeque::from([start]);
+        while let Some(pos) = q.pop_front() {
+            for n in self.grid.neighbours(pos) {
+                if !seen.contains(&n) && self.grid.at(&n).is_some_and(&passable) {
+                    seen.insert(n);
+                    q.push_back(n);
+                }
+            }
+        }
+        seen
+    }
+
+    // The real area covered by the compressed cells.
+    pub fn area<'a>(&self, cells: impl IntoIterator<Item = &'a GridPos>) -> i64 {
+        cells.into_iter().map(|pos| self.cell_area(pos)).sum()
+    }
+
+    // The real area covered by cells matching the predicate.
+    pub fn area_where<Pred>(&self, pred: Pred) -> i64
+    where
+        Pred: Fn(Item) -> bool,
+    {
+        self.grid
+            .iter_pair()
+            .filter(|(_, item)| pred(*item))
+            .map(|(pos, _)| self.cell_area(&pos))
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dir::Dir4, geometry::Polygon};
+
+    const DIG_PLAN: &str = "R 6 (#70c710)\nD 5 (#0dc571)\nL 2 (#5713f0)\nD 2 (#d2c081)\nR 2 (#59c680)\nD 2 (#411b91)\nL 5 (#8ceee2)\nU 2 (#caa173)\nL 1 (#1b58a2)\nU 2 (#caa171)\nR 2 (#7807d2)\nU 3 (#a77fa3)\nL 2 (#015232)\nU 2 (#7a21e3)";
+
+    fn lagoon(steps: Vec<(Dir4, i64)>) -> i64 {
+        let trench = Polygon::from_steps(GridPos::new(0, 0), steps);
+        let mut g = CompressedGrid::<char, Dir4>::new(trench.vertices(), '.');
+        for (a, b) in trench.edges() {
+            g.fill(&a, &b, '#').unwrap();
+        }
+        let outside = g.flood_fill(GridPos::new(0, 0), |c| c == '.');
+        g.area_where(|_| true) - g.area(&outside)
+    }
+
+    #[test]
+    fn test_axis() {
+        let axis = CompressedAxis::new([10, 2, 5, 6]);
+        assert_eq!(axis.len(), 8);
+        assert_eq!(axis.compress(1), Some(0));
+        assert_eq!(axis.compress(2), Some(1));
+        assert_eq!(axis.compress(4), Some(2));
+        assert_eq!(axis.size(2), 2);
+        assert_eq!(axis.real(5), 7);
+        assert_eq!(axis.compress(6), Some(4));
+        assert_eq!(axis.compress(9), Some(5));
+        assert_eq!(axis.compress(11), Some(7));
+        assert_eq!(axis.compress(12), None);
+        assert_eq!(axis.compress(0), None);
+    }
+
+    #[test]
+    fn test_lagoon() {
+        let to_dir = |c| match c {
+            'R' | '0' => Dir4::E,
+            'D' | '1' => Dir4::S,
+            'L' | '2' => Dir4::W,
+            _ => Dir4::N,
+        };
+        let part1 = DIG_PLAN
+            .lines()
+            .map(|line| {
+                let mut it = line.split(' ');
+                let dir = to_dir(it.next().unwrap().chars().next().unwrap());
+                (dir, it.next().unwrap().parse().unwrap())
+            })
+            .collect();
+        assert_eq!(lagoon(part1), 62);
+
+        let part2 = DIG_PLAN
+            .lines()
+            .map(|line| {
+                let hex = &line[line.len() - 7..line.len() - 1];
+                let dir = to_dir(hex.chars().last().unwrap());
+                (dir, i64::from_str_radix(&hex[..5], 16).unwrap())
+            })
+            .collect();
+        assert_eq!(lagoon(part2), 952408144115);
+    }
+}
@@ -0,0 +1,153 @@
+use enum_iterator::Sequence;
+
+use super::{Grid, GridPos};
+use crate::{dir::Dir, grif::Graph, hex::Hex};
+
+// A hexagon shaped map of hexes within `radius` of the origin, stored in a
+// square Grid indexed by the axial coordinates.
+#[derive(Debug, Clone)]
+pub struct HexGrid<Item, D>
+where
+    Item: Copy + Eq,
+    D: Dir,
+{
+    radius: i64,
+    grid: Grid<Item, D>,
+}
+
+fn in_hexagon<Item, D>(g: &Grid<Item, D>, _a: &GridPos, b: &GridPos) -> bool
+where
+    Item: Copy + Eq,
+    D: Dir + Sequence,
+{
+    let radius = (g.width() as i64 - 1) / 2;
+    Hex::new(b.x - radius, b.y - radius).distance(&Hex::ORIGIN) <= radius
+}
+
+impl<Item, D> HexGrid<Item, D>
+where
+    Item: Copy + Eq,
+    D: Dir + Sequence,
+{
+    pub fn new(item: Item, radius: usize) -> HexGrid<Item, D> {
+        let size = 2 * radius + 1;
+        HexGrid {
+            radius: radius as i64,
+            grid: Grid::new_walkable(item, size, size, in_hexagon),
+        }
+    }
+
+    pub fn radius(&self) -> usize {
+        self.radius as usize
+    }
+
+    pub fn grid(&self) -> &Grid<Item, D> {
+        &self.grid
+    }
+
+    fn grid_pos(&self, hex: &Hex) -> GridPos {
+        GridPos::new(hex.q + self.radius, hex.r + self.radius)
+    }
+
+    fn hex(&self, pos: &GridPos) -> Hex {
+        Hex::new(pos.x - self.radius, pos.y - self.radius)
+    }
+
+    pub fn is_valid(&self, hex: &Hex) -> bool {
+        hex.distance(&Hex::ORIGIN) <= self.radius
+    }
+
+    pub fn value(&self, hex: &Hex) -> Option<&Item> {
+        if self.is_valid(hex) {
+            self.grid.value(&self.grid_pos(hex))
+        } else {
+            None
+        }
+    }
+
+    pub fn at(&self, hex: &Hex) -> Option<Item> {
+        self.value(hex).copied()
+    }
+
+    pub fn set(&mut self, hex: &Hex, val: Item) -> Option<Item> {
+        if self.is_valid(hex) {
+            let pos = self.grid_pos(hex);
+            self.grid.set(&pos, val)
+        } else {
+            None
+        }
+    }
+
+    pub fn iter_hex(&self) -> impl Iterator<Item = Hex> + '_ {
+        Hex::ORIGIN.spiral(self.radius as usize)
+    }
+
+    pub fn iter_pair(&self) -> impl Iterator<Item = (Hex, Item)> + '_ {
+        self.iter_hex()
+            .filter_map(|hex| Some((hex, self.at(&hex)?)))
+    }
+
+    pub fn neighbours(&self, hex: Hex) -> impl Iterator<Item = Hex> + '_ {
+        let pos = self.grid_pos(&hex);
+        self.grid.neighbours(pos).map(|n| self.hex(&n))
+    }
+}
+
+impl<Item, D> Graph for HexGrid<Item, D>
+where
+    Item: Copy + Eq,
+    D: Dir + Sequence,
+{
+    type NodeId = Hex;
+    type Weight = usize;
+    type NodeValue = Item;
+
+    fn node(&self, id: &Self::NodeId) -> Option<&Self::NodeValue> {
+        self.value(id)
+    }
+
+    fn nodes(&self) -> impl Iterator<Item = Self::NodeId> {
+        self.iter_hex()
+    }
+
+    fn name(&self) -> String {
+        "hex".to_string()
+    }
+
+    fn node_edges(&self, node: Self::NodeId) -> impl Iterator<Item = (Self::NodeId, Self::Weight)> {
+        self.neighbours(node).map(|n| (n, 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dir::{DirHexFlat, DirHexPointy};
+
+    #[test]
+    fn test_hex_grid() {
+        let mut g = HexGrid::<char, DirHexPointy>::new('.', 3);
+        assert_eq!(g.nodes().count(), 37);
+        assert_eq!(g.degree(Hex::ORIGIN), 6);
+        assert_eq!(g.degree(Hex::new(3, 0)), 3);
+        assert_eq!(g.at(&Hex::new(3, 1)), None);
+        assert_eq!(g.set(&Hex::new(-3, 1), '#'), Some('.'));
+        assert_eq!(g.at(&Hex::new(-3, 1)), Some('#'));
+
+        let corner = Hex::new(3, -3);
+        assert!(g
+            .bfs(corner)
+            .all(|(hex, depth)| hex.distance(&corner) == depth as i64));
+        assert_eq!(g.bfs(corner).count(), 37);
+    }
+
+    #[test]
+    fn test_hex_grid_flat() {
+        let g = HexGrid::<u8, DirHexFlat>::new(0, 1);
+        let mut n = g.neighbours(Hex::ORIGIN).collect::<Vec<_>>();
+        n.sort();
+        let mut expected = Hex::ORIGIN.ring(1).collect::<Vec<_>>();
+        expected.sort();
+        assert_eq!(n, expected);
+    }
+}
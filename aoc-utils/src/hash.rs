@@ -0,0 +1,442 @@
+// Hashes and pseudo-random sequences from the puzzles, with a miner for the
+// first inputs whose hash passes a test, and dense indexing of windows of
+// differences in a sequence.
+use std::{collections::VecDeque, ops::Range, thread};
+
+// The HASH of 2023 day 15.
+pub fn holiday_hash(s: &str) -> u8 {
+    s.bytes()
+        .fold(0u8, |h, b| h.wrapping_add(b).wrapping_mul(17))
+}
+
+// The HASHMAP of 2023 day 15: 256 boxes of labelled lenses.
+#[derive(Debug, Clone)]
+pub struct LensBoxes {
+    boxes: Vec<Vec<(String, u32)>>,
+}
+
+impl Default for LensBoxes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LensBoxes {
+    pub fn new() -> LensBoxes {
+        LensBoxes {
+            boxes: vec![Vec::new(); 256],
+        }
+    }
+
+    // Apply a step like "rn=1", which puts or replaces a lens, or "cm-",
+    // which removes one. Returns None for anything else.
+    pub fn apply(&mut self, step: &str) -> Option<()> {
+        if let Some(label) = step.strip_suffix('-') {
+            self.boxes[holiday_hash(label) as usize].retain(|(l, _)| l != label);
+            return Some(());
+        }
+        let (label, focal) = step.split_once('=')?;
+        let focal = focal.parse().ok()?;
+        let lenses = &mut self.boxes[holiday_hash(label) as usize];
+        match lenses.iter_mut().find(|(l, _)| l == label) {
+            Some(lens) => lens.1 = focal,
+            None => lenses.push((label.to_string(), focal)),
+        }
+        Some(())
+    }
+
+    pub fn lenses(&self, b: u8) -> &[(String, u32)] {
+        &self.boxes[b as usize]
+    }
+
+    pub fn focusing_power(&self) -> u32 {
+        self.boxes
+            .iter()
+            .enumerate()
+            .flat_map(|(b, lenses)| {
+                lenses
+                    .iter()
+                    .enumerate()
+                    .map(move |(slot, (_, focal))| (b as u32 + 1) * (slot as u32 + 1) * focal)
+            })
+            .sum()
+    }
+}
+
+// The next secret number of 2024 day 22.
+pub fn next_secret(secret: u64) -> u64 {
+    const PRUNE: u64 = 16777216;
+    let secret = ((secret * 64) ^ secret) % PRUNE;
+    let secret = ((secret / 32) ^ secret) % PRUNE;
+    ((secret * 2048) ^ secret) % PRUNE
+}
+
+// The secret numbers from a seed, starting with the seed itself.
+pub fn secrets(seed: u64) -> impl Iterator<Item = u64> {
+    std::iter::successors(Some(seed), |&s| Some(next_secret(s)))
+}
+
+// Rounds of the knot tying of 2017 day 10 on a list of 0..size, without
+// the extra lengths of the full hash.
+pub fn knot_rounds(size: usize, lengths: &[usize], rounds: usize) -> Vec<usize> {
+    let mut list = (0..size).collect::<Vec<_>>();
+    let (mut pos, mut skip) = (0, 0);
+    for _ in 0..rounds {
+        for &length in lengths {
+            list.rotate_left(pos);
+            list[..length].reverse();
+            list.rotate_right(pos);
+            pos = (pos + length + skip) % size;
+            skip += 1;
+        }
+    }
+    list
+}
+
+// The dense knot hash of 2017 day 10.
+pub fn knot_hash(input: &str) -> [u8; 16] {
+    let lengths = input
+        .bytes()
+        .map(|b| b as usize)
+        .chain([17, 31, 73, 47, 23])
+        .collect::<Vec<_>>();
+    let sparse = knot_rounds(256, &lengths, 64);
+    let mut dense = [0; 16];
+    for (d, block) in dense.iter_mut().zip(sparse.chunks(16)) {
+        *d = block.iter().fold(0, |x, &v| x ^ v as u8);
+    }
+    dense
+}
+
+const MD5_SHIFTS: [u32; 16] = [7, 12, 17, 22, 5, 9, 14, 20, 4, 11, 16, 23, 6, 10, 15, 21];
+
+const MD5_CONSTANTS: [u32; 64] = [
+    0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+    0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+    0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa, 0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+    0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed, 0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+    0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c, 0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+    0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+    0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+    0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391,
+];
+
+pub fn md5(data: &[u8]) -> [u8; 16] {
+    let mut message = data.to_vec();
+    message.push(0x80);
+    // Pad to 8 bytes short of a whole block, leaving room for the length
+    message.resize((message.len() + 8).next_multiple_of(64) - 8, 0);
+    message.extend((data.len() as u64 * 8).to_le_bytes());
+
+    let mut state: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+    for chunk in message.chunks_exact(64) {
+        let words: [u32; 16] = std::array::from_fn(|i| {
+            u32::from_le_bytes(chunk[4 * i..4 * i + 4].try_into().unwrap())
+        });
+        let [mut a, mut b, mut c, mut d] = state;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let f = f
+                .wrapping_add(a)
+                .wrapping_add(MD5_CONSTANTS[i])
+                .wrapping_add(words[g]);
+            let shift = MD5_SHIFTS[(i / 16) * 4 + i % 4];
+            (a, d, c) = (d, c, b);
+            b = b.wrapping_add(f.rotate_left(shift));
+        }
+        for (s, v) in state.iter_mut().zip([a, b, c, d]) {
+            *s = s.wrapping_add(v);
+        }
+    }
+    let mut digest = [0; 16];
+    for (bytes, s) in digest.chunks_exact_mut(4).zip(state) {
+        bytes.copy_from_slice(&s.to_le_bytes());
+    }
+    digest
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+// The number of leading zero hex digits.
+pub fn leading_zero_digits(bytes: &[u8]) -> usize {
+    let zero_bytes = bytes.iter().take_while(|&&b| b == 0).count();
+    match bytes.get(zero_bytes) {
+        Some(b) if b >> 4 == 0 => 2 * zero_bytes + 1,
+        _ => 2 * zero_bytes,
+    }
+}
+
+// Evaluate f over a range split across threads, keeping the results in order.
+pub fn batch<T: Send>(range: Range<u64>, threads: usize, f: impl Fn(u64) -> T + Sync) -> Vec<T> {
+    let threads = threads.max(1) as u64;
+    let chunk = (range.end.saturating_sub(range.start))
+        .div_ceil(threads)
+        .max(1);
+    let f = &f;
+    thread::scope(|scope| {
+        let handles = (range.start..range.end)
+            .step_by(chunk as usize)
+            .map(|start| {
+                let end = (start + chunk).min(range.end);
+                scope.spawn(move || (start..end).map(f).collect::<Vec<_>>())
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect()
+    })
+}
+
+// The numbers from a start which pass a test, in order, found by testing
+// batches in parallel.
+pub struct Miner<F> {
+    next: u64,
+    batch_size: u64,
+    threads: usize,
+    test: F,
+    found: VecDeque<u64>,
+}
+
+impl<F: Fn(u64) -> bool + Sync> Miner<F> {
+    pub fn new(start: u64, threads: usize, test: F) -> Miner<F> {
+        Miner {
+            next: start,
+            batch_size: 1024 * threads.max(1) as u64,
+            threads,
+            test,
+            found: VecDeque::new(),
+        }
+    }
+
+    // Numbers before this have all been tested
+    pub fn tested(&self) -> u64 {
+        self.next
+    }
+}
+
+impl<F: Fn(u64) -> bool + Sync> Iterator for Miner<F> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        while self.found.is_empty() {
+            let range = self.next..self.next.checked_add(self.batch_size)?;
+            let test = &self.test;
+            let passed = batch(range.clone(), self.threads, |n| test(n).then_some(n));
+            self.found.extend(passed.into_iter().flatten());
+            self.next = range.end;
+        }
+        self.found.pop_front()
+    }
+}
+
+// Numbers which make the MD5 of the salt followed by the number start with
+// some zero hex digits.
+pub fn mine_md5(
+    salt: &str,
+    zeros: usize,
+    threads: usize,
+) -> Miner<impl Fn(u64) -> bool + Sync + '_> {
+    Miner::new(0, threads, move |n| {
+        leading_zero_digits(&md5(format!("{salt}{n}").as_bytes())) >= zeros
+    })
+}
+
+// Windows of consecutive differences in a sequence, numbered densely so that
+// anything kept per window can be a Vec indexed by the window's key.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DeltaWindows {
+    width: usize,
+    min: i64,
+    span: usize,
+}
+
+impl DeltaWindows {
+    // Windows of width differences, each between min and max. None unless
+    // there is at least one window, and the keys fit in a usize.
+    pub fn new(width: usize, min: i64, max: i64) -> Option<DeltaWindows> {
+        if width == 0 || max < min {
+            return None;
+        }
+        let span = usize::try_from(max.checked_sub(min)?)
+            .ok()?
+            .checked_add(1)?;
+        span.checked_pow(u32::try_from(width).ok()?)?;
+        Some(DeltaWindows { width, min, span })
+    }
+
+    // The number of possible keys
+    pub fn len(&self) -> usize {
+        self.span.pow(self.width as u32)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn key(&self, deltas: &[i64]) -> Option<usize> {
+        if deltas.len() != self.width {
+            return None;
+        }
+        deltas.iter().try_fold(0, |key, &d| {
+            let digit = d
+                .checked_sub(self.min)
+                .and_then(|d| usize::try_from(d).ok())
+                .filter(|&d| d < self.span)?;
+            Some(key * self.span + digit)
+        })
+    }
+
+    pub fn deltas(&self, key: usize) -> Vec<i64> {
+        let mut deltas = (0..self.width)
+            .scan(key, |key, _| {
+                let digit = *key % self.span;
+                *key /= self.span;
+                Some(digit as i64 + self.min)
+            })
+            .collect::<Vec<_>>();
+        deltas.reverse();
+        deltas
+    }
+
+    // The key of each window of differences in the values, with the index of
+    // the value ending the window. Windows with a difference out of range are
+    // skipped.
+    pub fn keys<'a>(&'a self, values: &'a [i64]) -> impl Iterator<Item = (usize, usize)> + 'a {
+        let len = self.len();
+        values
+            .windows(2)
+            .enumerate()
+            // The key so far, and how many differences in a row it covers
+            .scan((0, 0), move |(key, run), (i, pair)| {
+                let digit = pair[1]
+                    .checked_sub(pair[0])
+                    .and_then(|d| d.checked_sub(self.min))
+                    .and_then(|d| usize::try_from(d).ok())
+                    .filter(|&d| d < self.span);
+                match digit {
+                    Some(digit) => {
+                        *key = (*key * self.span + digit) % len;
+                        *run += 1;
+                    }
+                    None => (*key, *run) = (0, 0),
+                }
+                Some((*run >= self.width).then_some((i + 1, *key)))
+            })
+            .flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_holiday_hash() {
+        assert_eq!(holiday_hash("HASH"), 52);
+        let steps = "rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7";
+        let total = steps
+            .split(',')
+            .map(|s| holiday_hash(s) as u32)
+            .sum::<u32>();
+        assert_eq!(total, 1320);
+
+        let mut boxes = LensBoxes::new();
+        for step in steps.split(',') {
+            boxes.apply(step).unwrap();
+        }
+        assert_eq!(
+            boxes.lenses(0),
+            &[("rn".to_string(), 1), ("cm".to_string(), 2)]
+        );
+        assert_eq!(boxes.focusing_power(), 145);
+        assert!(boxes.apply("bad").is_none());
+    }
+
+    #[test]
+    fn test_knot_and_md5() {
+        let list = knot_rounds(5, &[3, 4, 1, 5], 1);
+        assert_eq!(list, vec![3, 4, 2, 1, 0]);
+        assert_eq!(to_hex(&knot_hash("")), "a2582a3a0e66e6e86e3812dcb672a272");
+        assert_eq!(
+            to_hex(&knot_hash("AoC 2017")),
+            "33efeb34ea91902bb2f59c9920caa6cd"
+        );
+
+        assert_eq!(to_hex(&md5(b"")), "d41d8cd98f00b204e9800998ecf8427e");
+        let fox = md5(b"The quick brown fox jumps over the lazy dog");
+        assert_eq!(to_hex(&fox), "9e107d9d372bb6826bd81d3542a419d6");
+        let long = md5(&[b'a'; 120]);
+        assert_eq!(to_hex(&long), "5f61c0ccad4cac44c75ff505e1f1e537");
+        assert_eq!(leading_zero_digits(&md5(b"abcdef609043")), 5);
+
+        let mut miner = mine_md5("abcdef", 5, 4);
+        assert_eq!(miner.next(), Some(609043));
+        assert!(miner.tested() > 609043);
+        assert_eq!(
+            batch(0..10, 3, |n| n * n),
+            (0..10).map(|n| n * n).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_secrets() {
+        let first = secrets(123).skip(1).take(3).collect::<Vec<_>>();
+        assert_eq!(first, vec![15887950, 16495136, 527345]);
+        let total = batch(0..4, 4, |i| {
+            secrets([1, 10, 100, 2024][i as usize]).nth(2000).unwrap()
+        });
+        assert_eq!(total.iter().sum::<u64>(), 37327623);
+
+        // Bananas for the first sale after each run of four price changes
+        let windows = DeltaWindows::new(4, -9, 9).unwrap();
+        assert_eq!(windows.len(), 130321);
+        let mut bananas = vec![0; windows.len()];
+        let mut seen = vec![usize::MAX; windows.len()];
+        for (buyer, seed) in [1, 2, 3, 2024].into_iter().enumerate() {
+            let prices = secrets(seed)
+                .take(2001)
+                .map(|s| (s % 10) as i64)
+                .collect::<Vec<_>>();
+            for (i, key) in windows.keys(&prices) {
+                if seen[key] != buyer {
+                    seen[key] = buyer;
+                    bananas[key] += prices[i];
+                }
+            }
+        }
+        let (best, &most) = bananas.iter().enumerate().max_by_key(|&(_, b)| b).unwrap();
+        assert_eq!(most, 23);
+        assert_eq!(windows.deltas(best), vec![-2, 1, -1, 3]);
+        assert_eq!(windows.key(&[-2, 1, -1, 3]), Some(best));
+        assert_eq!(windows.key(&[-2, 1, -1, 10]), None);
+    }
+
+    #[test]
+    fn test_delta_windows() {
+        assert_eq!(DeltaWindows::new(0, -9, 9), None);
+        assert_eq!(DeltaWindows::new(2, 9, -9), None);
+        assert_eq!(DeltaWindows::new(64, -9, 9), None);
+        assert_eq!(DeltaWindows::new(1, i64::MIN, i64::MAX), None);
+
+        let windows = DeltaWindows::new(2, -1, 1).unwrap();
+        assert_eq!(windows.len(), 9);
+        assert!(!windows.is_empty());
+        // The jump from 2 to 7 is out of range, so no window spans it
+        let keys = windows.keys(&[0, 1, 2, 7, 6, 6, 7]).collect::<Vec<_>>();
+        let expected = [(2, &[1, 1]), (5, &[-1, 0]), (6, &[0, 1])]
+            .map(|(i, deltas)| (i, windows.key(deltas).unwrap()));
+        assert_eq!(keys, expected);
+        assert_eq!(windows.keys(&[0, i64::MAX]).count(), 0);
+        let windows = DeltaWindows::new(1, -9, 9).unwrap();
+        assert_eq!(windows.key(&[i64::MAX]), None);
+        assert_eq!(windows.key(&[i64::MIN]), None);
+    }
+}
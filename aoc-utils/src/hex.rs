@@ -0,0 +1,180 @@
+use std::{
+    fmt::Display,
+    ops::{Add, Sub},
+};
+
+use crate::{
+    dir::{Dir, DirHexFlat, DirHexPointy, DirVec},
+    pos2d::Pos2d,
+};
+
+// A hex cell in axial coordinates. The implied cube coordinate is s = -q - r.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Hex {
+    pub q: i64,
+    pub r: i64,
+}
+
+impl Display for Hex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<{},{}>", self.q, self.r)
+    }
+}
+
+impl Hex {
+    pub const ORIGIN: Hex = Hex { q: 0, r: 0 };
+
+    pub const fn new(q: i64, r: i64) -> Hex {
+        Hex { q, r }
+    }
+
+    pub fn from_cube(q: i64, r: i64, s: i64) -> Option<Hex> {
+        if q + r + s == 0 {
+            Some(Hex { q, r })
+        } else {
+            None
+        }
+    }
+
+    pub fn s(&self) -> i64 {
+        -self.q - self.r
+    }
+
+    pub fn distance(&self, other: &Hex) -> i64 {
+        let dq = (self.q - other.q).abs();
+        let dr = (self.r - other.r).abs();
+        let ds = (self.s() - other.s()).abs();
+        (dq + dr + ds) / 2
+    }
+
+    pub fn neighbour<D: Dir>(&self, dir: D) -> Hex {
+        *self + dir.to_vec2d()
+    }
+
+    // Follow a list of directions from this hex
+    pub fn walk<D: Dir>(&self, path: impl IntoIterator<Item = D>) -> Hex {
+        path.into_iter().fold(*self, |hex, d| hex.neighbour(d))
+    }
+
+    // All hexes at exactly `radius` steps away.
+    pub fn ring(&self, radius: usize) -> impl Iterator<Item = Hex> {
+        let radius = radius as i64;
+        let start = *self + DirHexPointy::SW.to_vec2d() * radius;
+        let steps = if radius == 0 { 1 } else { 6 * radius };
+        (0..steps).scan(start, move |hex, i| {
+            let current = *hex;
+            // Walk anticlockwise from the SW corner: E, NE, NW, W, SW, SE
+            let side = DirHexPointy::from_i(7 - (i / radius.max(1)) as usize);
+            *hex = hex.neighbour(side);
+            Some(current)
+        })
+    }
+
+    // All hexes within `radius` steps, in order of increasing distance.
+    pub fn spiral(&self, radius: usize) -> impl Iterator<Item = Hex> {
+        let centre = *self;
+        (0..=radius).flat_map(move |r| centre.ring(r))
+    }
+
+    pub fn to_pos2d(&self) -> Pos2d<i64> {
+        Pos2d::new(self.q, self.r)
+    }
+}
+
+impl From<Pos2d<i64>> for Hex {
+    fn from(pos: Pos2d<i64>) -> Self {
+        Hex::new(pos.x, pos.y)
+    }
+}
+
+impl Add<DirVec> for Hex {
+    type Output = Self;
+
+    fn add(self, rhs: DirVec) -> Self::Output {
+        Hex::new(self.q + rhs.x, self.r + rhs.y)
+    }
+}
+
+impl Add<DirHexPointy> for Hex {
+    type Output = Self;
+
+    fn add(self, rhs: DirHexPointy) -> Self::Output {
+        self.neighbour(rhs)
+    }
+}
+
+impl Add<DirHexFlat> for Hex {
+    type Output = Self;
+
+    fn add(self, rhs: DirHexFlat) -> Self::Output {
+        self.neighbour(rhs)
+    }
+}
+
+impl Sub<Hex> for Hex {
+    type Output = DirVec;
+
+    fn sub(self, rhs: Hex) -> Self::Output {
+        DirVec::new(self.q - rhs.q, self.r - rhs.r)
+    }
+}
+
+// Parses directions written without separators, e.g. "nwwswee"
+pub fn parse_pointy_path(s: &str) -> Option<Vec<DirHexPointy>> {
+    let mut path = Vec::new();
+    let mut rest = s.trim();
+    while !rest.is_empty() {
+        let len = if rest.starts_with('n') || rest.starts_with('s') {
+            2
+        } else {
+            1
+        };
+        path.push(DirHexPointy::parse(rest.get(..len)?)?);
+        rest = &rest[len..];
+    }
+    Some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    fn flat_distance(path: &str) -> i64 {
+        let path = path.split(',').map(|s| DirHexFlat::parse(s).unwrap());
+        Hex::ORIGIN.walk(path).distance(&Hex::ORIGIN)
+    }
+
+    #[test]
+    fn test_distance() {
+        assert_eq!(flat_distance("ne,ne,ne"), 3);
+        assert_eq!(flat_distance("ne,ne,sw,sw"), 0);
+        assert_eq!(flat_distance("ne,ne,s,s"), 2);
+        assert_eq!(flat_distance("se,sw,se,sw,sw"), 3);
+    }
+
+    #[test]
+    fn test_pointy_path() {
+        let path = parse_pointy_path("nwwswee").unwrap();
+        assert_eq!(path.len(), 5);
+        assert_eq!(Hex::ORIGIN.walk(path), Hex::ORIGIN);
+        let esew = Hex::ORIGIN.walk(parse_pointy_path("esew").unwrap());
+        assert_eq!(esew, Hex::ORIGIN + DirHexPointy::SE);
+        assert_eq!(parse_pointy_path("ex"), None);
+    }
+
+    #[test]
+    fn test_ring() {
+        let centre = Hex::new(3, -1);
+        assert_eq!(centre.ring(0).collect::<Vec<_>>(), vec![centre]);
+        for r in 1..4 {
+            let ring = centre.ring(r).collect::<HashSet<_>>();
+            assert_eq!(ring.len(), 6 * r);
+            assert!(ring.iter().all(|h| h.distance(&centre) == r as i64));
+        }
+        let spiral = centre.spiral(2).collect::<Vec<_>>();
+        assert_eq!(spiral.len(), 19);
+        assert_eq!(spiral.iter().collect::<HashSet<_>>().len(), 19);
+    }
+}
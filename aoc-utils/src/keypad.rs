@@ -0,0 +1,315 @@
+// Keypads operated through a stack of controllers. The keys of each pad are
+// pressed by a pointer driven from the directional pad above it, which starts
+// on the pad's start key, and the last pad is pressed directly.
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::{
+    dir::{Dir, Dir4},
+    grud::{Grid, GridPos},
+    memo::Memo,
+};
+
+pub type KeyGrid = Grid<char, Dir4>;
+
+pub const ACTIVATE: char = 'A';
+
+// The key on a directional pad that moves a pointer in a direction.
+pub fn dir_key(d: Dir4) -> char {
+    match d {
+        Dir4::N => '^',
+        Dir4::E => '>',
+        Dir4::S => 'v',
+        Dir4::W => '<',
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Keypad {
+    grid: KeyGrid,
+    keys: HashMap<char, GridPos>,
+    cells: HashSet<GridPos>,
+    // The key the pointer starts on
+    start: char,
+}
+
+impl Keypad {
+    // A keypad from a grid of keys, where the gap is not a key and the
+    // pointer must never be over it. None if a key appears twice or the
+    // start isn't a key.
+    pub fn new(grid: KeyGrid, gap: char, start: char) -> Option<Keypad> {
+        let mut keys = HashMap::new();
+        for (pos, k) in grid.iter_pair().filter(|&(_, k)| k != gap) {
+            if keys.insert(k, pos).is_some() {
+                return None;
+            }
+        }
+        if !keys.contains_key(&start) {
+            return None;
+        }
+        let cells = keys.values().copied().collect();
+        Some(Keypad {
+            grid,
+            keys,
+            cells,
+            start,
+        })
+    }
+
+    pub fn parse(layout: &str, gap: char, start: char) -> Option<Keypad> {
+        Keypad::new(KeyGrid::parse(layout), gap, start)
+    }
+
+    // The numeric keypad of 2024 day 21.
+    pub fn numeric() -> Keypad {
+        Keypad::parse("789\n456\n123\n#0A", '#', ACTIVATE).unwrap()
+    }
+
+    // The directional keypad of 2024 day 21.
+    pub fn directional() -> Keypad {
+        Keypad::parse("#^A\n<v>", '#', ACTIVATE).unwrap()
+    }
+
+    pub fn grid(&self) -> &KeyGrid {
+        &self.grid
+    }
+
+    pub fn start(&self) -> char {
+        self.start
+    }
+
+    pub fn position(&self, key: char) -> Option<GridPos> {
+        self.keys.get(&key).copied()
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = char> + '_ {
+        self.keys.keys().copied()
+    }
+
+    // The shortest key sequences taking the pointer from one key to another,
+    // followed by pressing activate. Only sequences which move in each
+    // direction in a single run are included, as breaking up a run never
+    // takes fewer presses above, so there are few of them on any pad.
+    pub fn paths(&self, from: char, to: char) -> Vec<String> {
+        let (Some(&from), Some(&to)) = (self.keys.get(&from), self.keys.get(&to)) else {
+            return Vec::new();
+        };
+        let distances = self.distances(to);
+        let mut paths = Vec::new();
+        self.walk(&distances, from, None, String::new(), &mut paths);
+        paths
+    }
+
+    // Moves from every key position to the target
+    fn distances(&self, to: GridPos) -> HashMap<GridPos, usize> {
+        let mut distances = HashMap::from([(to, 0)]);
+        let mut queue = VecDeque::from([to]);
+        while let Some(pos) = queue.pop_front() {
+            let d = distances[&pos];
+            for dir in Dir4::cw() {
+                let next = pos + dir.to_vec2d();
+                if self.cells.contains(&next) && !distances.contains_key(&next) {
+                    distances.insert(next, d + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+        distances
+    }
+
+    fn walk(
+        &self,
+        distances: &HashMap<GridPos, usize>,
+        pos: GridPos,
+        last: Option<Dir4>,
+        path: String,
+        paths: &mut Vec<String>,
+    ) {
+        let Some(&d) = distances.get(&pos) else {
+            return;
+        };
+        if d == 0 {
+            paths.push(path + &ACTIVATE.to_string());
+            return;
+        }
+        for dir in Dir4::cw() {
+            // A direction can only be taken again while its run continues
+            if last != Some(dir) && path.contains(dir_key(dir)) {
+                continue;
+            }
+            let next = pos + dir.to_vec2d();
+            if distances.get(&next) == Some(&(d - 1)) {
+                let path = format!("{path}{}", dir_key(dir));
+                self.walk(distances, next, Some(dir), path, paths);
+            }
+        }
+    }
+}
+
+// A keypad to type on, then the pads controlling it from the nearest to the
+// one pressed directly.
+#[derive(Debug, Clone)]
+pub struct Controllers {
+    pads: Vec<Keypad>,
+    memo: Memo<(usize, char, char), Option<usize>>,
+}
+
+impl Controllers {
+    pub fn new(pads: Vec<Keypad>) -> Controllers {
+        Controllers {
+            pads,
+            memo: Memo::new(),
+        }
+    }
+
+    // A target pad controlled through layers of copies of a controller.
+    pub fn stacked(target: Keypad, controller: Keypad, layers: usize) -> Controllers {
+        let mut pads = vec![target];
+        pads.extend(std::iter::repeat_n(controller, layers));
+        Controllers::new(pads)
+    }
+
+    pub fn pads(&self) -> &[Keypad] {
+        &self.pads
+    }
+
+    pub fn memo(&self) -> &Memo<(usize, char, char), Option<usize>> {
+        &self.memo
+    }
+
+    // The fewest presses on the last pad to type the sequence on the first,
+    // or None if some key can't be reached.
+    pub fn presses(&mut self, sequence: &str) -> Option<usize> {
+        press_cost(&self.pads, &mut self.memo, 0, sequence)
+    }
+
+    // A cheapest way to type the sequence, as the keys typed on each pad in
+    // turn, from the sequence itself to the presses on the last pad.
+    pub fn sequences(&mut self, sequence: &str) -> Option<Vec<String>> {
+        let mut sequences = vec![sequence.to_string()];
+        for layer in 0..self.pads.len() {
+            let typed = sequences.last().unwrap();
+            let mut from = self.pads[layer].start();
+            let mut next = String::new();
+            for to in typed.chars() {
+                let path = self.pads[layer]
+                    .paths(from, to)
+                    .into_iter()
+                    .filter_map(|p| {
+                        let cost = press_cost(&self.pads, &mut self.memo, layer + 1, &p)?;
+                        Some((cost, p))
+                    })
+                    .min_by_key(|&(cost, _)| cost)?
+                    .1;
+                next.push_str(&path);
+                from = to;
+            }
+            sequences.push(next);
+        }
+        Some(sequences)
+    }
+}
+
+// Presses to type the sequence on a layer's pad, where the layer past the
+// last pad is typed directly.
+fn press_cost(
+    pads: &[Keypad],
+    memo: &mut Memo<(usize, char, char), Option<usize>>,
+    layer: usize,
+    sequence: &str,
+) -> Option<usize> {
+    if layer == pads.len() {
+        return Some(sequence.len());
+    }
+    let mut from = pads[layer].start();
+    let mut total = 0;
+    for to in sequence.chars() {
+        total += memo.get_or_compute((layer, from, to), |memo| {
+            pads[layer]
+                .paths(from, to)
+                .iter()
+                .filter_map(|path| press_cost(pads, memo, layer + 1, path))
+                .min()
+        })?;
+        from = to;
+    }
+    Some(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CODES: [&str; 5] = ["029A", "980A", "179A", "456A", "379A"];
+
+    fn complexity(controllers: &mut Controllers) -> usize {
+        CODES
+            .iter()
+            .map(|code| {
+                let number = code.trim_end_matches('A').parse::<usize>().unwrap();
+                controllers.presses(code).unwrap() * number
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_keypad() {
+        let numeric = Keypad::numeric();
+        assert_eq!(numeric.position('0'), Some(GridPos::new(1, 3)));
+        assert_eq!(numeric.position('#'), None);
+        assert_eq!(numeric.paths('A', '0'), ["<A"]);
+        assert_eq!(numeric.paths('A', 'A'), ["A"]);
+        // Around the gap only
+        assert_eq!(numeric.paths('0', '1'), ["^<A"]);
+        assert_eq!(numeric.paths('A', '7'), ["^^^<<A"]);
+        let mut paths = numeric.paths('2', '9');
+        paths.sort();
+        assert_eq!(paths, [">^^A", "^^>A"]);
+        assert!(numeric.paths('A', 'x').is_empty());
+
+        assert!(Keypad::parse("12\n1A", '#', ACTIVATE).is_none());
+        assert!(Keypad::parse("12\n34", '#', ACTIVATE).is_none());
+    }
+
+    #[test]
+    fn test_controllers() {
+        let directional = Keypad::directional();
+        let mut controllers = Controllers::stacked(Keypad::numeric(), directional.clone(), 2);
+        assert_eq!(controllers.presses("029A"), Some(68));
+        assert_eq!(complexity(&mut controllers), 126384);
+        assert!(controllers.memo().stats().hits > 0);
+        assert_eq!(controllers.presses("02x"), None);
+
+        let sequences = controllers.sequences("029A").unwrap();
+        let lengths = sequences.iter().map(|s| s.len()).collect::<Vec<_>>();
+        assert_eq!(lengths, vec![4, 12, 28, 68]);
+
+        let mut controllers = Controllers::stacked(Keypad::numeric(), directional, 25);
+        assert_eq!(complexity(&mut controllers), 154115708116294);
+    }
+
+    #[test]
+    fn test_custom_layout() {
+        // A pad with a gap in the middle, which must be walked around
+        let ring = Keypad::parse("123\n4#5\n67A", '#', ACTIVATE).unwrap();
+        let mut paths = ring.paths('4', '5');
+        paths.sort();
+        assert_eq!(paths, ["^>>vA", "v>>^A"]);
+        let mut controllers = Controllers::stacked(ring, Keypad::directional(), 1);
+        let sequences = controllers.sequences("45").unwrap();
+        assert_eq!(controllers.presses("45"), Some(sequences[2].len()));
+
+        // A large pad with no activate key, starting from a corner
+        let key = |n: u32| char::from_u32(0xc0 + n).unwrap();
+        let layout = (0..10)
+            .map(|row| (0..10).map(|col| key(row * 10 + col)).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let big = Keypad::parse(&layout, ' ', key(0)).unwrap();
+        assert_eq!(big.position(ACTIVATE), None);
+        assert_eq!(big.paths(key(0), key(99)).len(), 2);
+        let mut controllers = Controllers::stacked(big, Keypad::directional(), 2);
+        let far = key(99).to_string();
+        assert!(controllers.presses(&far).is_some());
+        assert!(controllers.sequences(&far).is_some());
+    }
+}
@@ -1,12 +1,33 @@
+pub mod automaton;
+pub mod bits;
+pub mod circuit;
+pub mod circular;
 pub mod dir;
+pub mod expr;
+pub mod geometry;
+pub mod grid3d;
 pub mod grif;
 pub mod grud;
+pub mod hash;
+pub mod hex;
+pub mod keypad;
+pub mod linalg;
 pub mod lust;
+pub mod math;
+pub mod memo;
+pub mod nested;
+pub mod network;
+pub mod physics;
 pub mod pos2d;
 pub mod pos3d;
+pub mod radix;
+pub mod ranges;
 pub mod run;
+pub mod search;
+pub mod sequence;
 pub mod str;
 pub mod uterators;
 pub mod vec2d;
 pub mod vec3d;
 pub mod vecnd;
+pub mod vm;
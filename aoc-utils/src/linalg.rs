@@ -0,0 +1,365 @@
+pub mod gf2;
+pub mod ilp;
+pub mod rational;
+
+use std::{
+    fmt::Display,
+    ops::{Add, Div, Index, IndexMut, Mul, Neg, Sub},
+};
+
+use num_traits::{One, PrimInt, Signed, Zero};
+
+use rational::Rational;
+
+// The operations needed for exact elimination. Rational<T> is the usual choice,
+// although f64 works too if rounding is acceptable.
+pub trait Field:
+    Copy
+    + PartialEq
+    + Zero
+    + One
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+{
+}
+
+impl<T> Field for T where
+    T: Copy
+        + PartialEq
+        + Zero
+        + One
+        + Add<Output = T>
+        + Sub<Output = T>
+        + Mul<Output = T>
+        + Div<Output = T>
+        + Neg<Output = T>
+{
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Matrix<T> {
+    rows: usize,
+    cols: usize,
+    data: Vec<T>,
+}
+
+impl<T: Display> Display for Matrix<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for r in 0..self.rows {
+            write!(f, "[")?;
+            for c in 0..self.cols {
+                if c > 0 {
+                    write!(f, " ")?;
+                }
+                write!(f, "{}", self.data[r * self.cols + c])?;
+            }
+            writeln!(f, "]")?;
+        }
+        Ok(())
+    }
+}
+
+impl<T> Index<(usize, usize)> for Matrix<T> {
+    type Output = T;
+
+    fn index(&self, (r, c): (usize, usize)) -> &Self::Output {
+        &self.data[r * self.cols + c]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Matrix<T> {
+    fn index_mut(&mut self, (r, c): (usize, usize)) -> &mut Self::Output {
+        &mut self.data[r * self.cols + c]
+    }
+}
+
+impl<T: Field> Matrix<T> {
+    pub fn new(rows: usize, cols: usize) -> Matrix<T> {
+        Matrix {
+            rows,
+            cols,
+            data: vec![T::zero(); rows * cols],
+        }
+    }
+
+    pub fn identity(n: usize) -> Matrix<T> {
+        let mut m = Matrix::new(n, n);
+        (0..n).for_each(|i| m[(i, i)] = T::one());
+        m
+    }
+
+    pub fn from_rows<R: AsRef<[T]>>(rows: &[R]) -> Matrix<T> {
+        let cols = rows.first().map_or(0, |r| r.as_ref().len());
+        assert!(rows.iter().all(|r| r.as_ref().len() == cols));
+        Matrix {
+            rows: rows.len(),
+            cols,
+            data: rows
+                .iter()
+                .flat_map(|r| r.as_ref().iter().copied())
+                .collect(),
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn row(&self, r: usize) -> &[T] {
+        &self.data[r * self.cols..(r + 1) * self.cols]
+    }
+
+    pub fn transpose(&self) -> Matrix<T> {
+        let mut t = Matrix::new(self.cols, self.rows);
+        for r in 0..self.rows {
+            for c in 0..self.cols {
+                t[(c, r)] = self[(r, c)];
+            }
+        }
+        t
+    }
+
+    pub fn mul_vec(&self, v: &[T]) -> Vec<T> {
+        assert_eq!(v.len(), self.cols);
+        (0..self.rows)
+            .map(|r| {
+                self.row(r)
+                    .iter()
+                    .zip(v)
+                    .fold(T::zero(), |sum, (&a, &b)| sum + a * b)
+            })
+            .collect()
+    }
+
+    fn swap_rows(&mut self, a: usize, b: usize) {
+        if a != b {
+            for c in 0..self.cols {
+                self.data.swap(a * self.cols + c, b * self.cols + c);
+            }
+        }
+    }
+
+    // Gauss-Jordan elimination over the first `cols` columns.
+    // Returns the pivot columns.
+    fn eliminate(&mut self, cols: usize) -> Vec<usize> {
+        let mut pivots = Vec::new();
+        let mut row = 0;
+        for col in 0..cols {
+            if row >= self.rows {
+                break;
+            }
+            let Some(pivot) = (row..self.rows).find(|&r| !self[(r, col)].is_zero()) else {
+                continue;
+            };
+            self.swap_rows(pivot, row);
+            let p = self[(row, col)];
+            for c in 0..self.cols {
+                self[(row, c)] = self[(row, c)] / p;
+            }
+            for r in 0..self.rows {
+                let factor = self[(r, col)];
+                if r != row && !factor.is_zero() {
+                    for c in 0..self.cols {
+                        self[(r, c)] = self[(r, c)] - factor * self[(row, c)];
+                    }
+                }
+            }
+            pivots.push(col);
+            row += 1;
+        }
+        pivots
+    }
+
+    // The reduced row echelon form and its pivot columns.
+    pub fn rref(&self) -> (Matrix<T>, Vec<usize>) {
+        let mut m = self.clone();
+        let pivots = m.eliminate(self.cols);
+        (m, pivots)
+    }
+
+    pub fn rank(&self) -> usize {
+        self.rref().1.len()
+    }
+
+    pub fn determinant(&self) -> T {
+        assert_eq!(self.rows, self.cols);
+        // Forward elimination, keeping track of the pivot product
+        let mut m = self.clone();
+        let mut det = T::one();
+        for col in 0..self.cols {
+            let Some(pivot) = (col..self.rows).find(|&r| !m[(r, col)].is_zero()) else {
+                return T::zero();
+            };
+            if pivot != col {
+                m.swap_rows(pivot, col);
+                det = -det;
+            }
+            let p = m[(col, col)];
+            det = det * p;
+            for r in col + 1..self.rows {
+                let factor = m[(r, col)] / p;
+                for c in col..self.cols {
+                    m[(r, c)] = m[(r, c)] - factor * m[(col, c)];
+                }
+            }
+        }
+        det
+    }
+
+    pub fn inverse(&self) -> Option<Matrix<T>> {
+        assert_eq!(self.rows, self.cols);
+        let n = self.rows;
+        let mut aug = Matrix::new(n, 2 * n);
+        for r in 0..n {
+            for c in 0..n {
+                aug[(r, c)] = self[(r, c)];
+            }
+            aug[(r, n + r)] = T::one();
+        }
+        let pivots = aug.eliminate(n);
+        if pivots.len() < n {
+            return None;
+        }
+        let mut inv = Matrix::new(n, n);
+        for r in 0..n {
+            for c in 0..n {
+                inv[(r, c)] = aug[(r, n + c)];
+            }
+        }
+        Some(inv)
+    }
+
+    // Solve self * x = b. Returns None if there is no solution or the solution
+    // isn't unique.
+    pub fn solve(&self, b: &[T]) -> Option<Vec<T>> {
+        assert_eq!(b.len(), self.rows);
+        let mut aug = Matrix::new(self.rows, self.cols + 1);
+        for r in 0..self.rows {
+            for c in 0..self.cols {
+                aug[(r, c)] = self[(r, c)];
+            }
+            aug[(r, self.cols)] = b[r];
+        }
+        let pivots = aug.eliminate(self.cols);
+        let inconsistent = (pivots.len()..self.rows).any(|r| !aug[(r, self.cols)].is_zero());
+        if inconsistent || pivots.len() < self.cols {
+            return None;
+        }
+        Some((0..self.cols).map(|r| aug[(r, self.cols)]).collect())
+    }
+}
+
+impl<T: Field> Mul for &Matrix<T> {
+    type Output = Matrix<T>;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        assert_eq!(self.cols, rhs.rows);
+        let mut m = Matrix::new(self.rows, rhs.cols);
+        for r in 0..self.rows {
+            for c in 0..rhs.cols {
+                m[(r, c)] =
+                    (0..self.cols).fold(T::zero(), |sum, k| sum + self[(r, k)] * rhs[(k, c)]);
+            }
+        }
+        m
+    }
+}
+
+impl<T: PrimInt + Signed> Matrix<Rational<T>> {
+    pub fn from_ints<R: AsRef<[T]>>(rows: &[R]) -> Matrix<Rational<T>> {
+        let rows = rows
+            .iter()
+            .map(|r| r.as_ref().iter().map(|&v| Rational::from(v)).collect())
+            .collect::<Vec<Vec<_>>>();
+        Matrix::from_rows(&rows)
+    }
+
+    // Solve self * x = b, only accepting a unique, all integer solution.
+    pub fn solve_integer(&self, b: &[T]) -> Option<Vec<T>> {
+        let b = b.iter().map(|&v| Rational::from(v)).collect::<Vec<_>>();
+        self.solve(&b)?.iter().map(|x| x.to_integer()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Q = Rational<i128>;
+
+    #[test]
+    fn test_matrix() {
+        let m = Matrix::<Q>::from_ints(&[[2, 1], [7, 4]]);
+        assert_eq!(m.determinant(), Q::from(1));
+        let inv = m.inverse().unwrap();
+        assert_eq!(inv, Matrix::from_ints(&[[4, -1], [-7, 2]]));
+        assert_eq!(&m * &inv, Matrix::identity(2));
+        assert_eq!(m.transpose()[(0, 1)], Q::from(7));
+
+        let singular = Matrix::<Q>::from_ints(&[[1, 2, 3], [4, 5, 6], [7, 8, 9]]);
+        assert_eq!(singular.determinant(), Q::zero());
+        assert_eq!(singular.inverse(), None);
+        assert_eq!(singular.rank(), 2);
+        let (rref, pivots) = singular.rref();
+        assert_eq!(pivots, vec![0, 1]);
+        assert_eq!(rref.row(0), &[Q::from(1), Q::zero(), Q::from(-1)]);
+
+        let m = Matrix::<Q>::from_ints(&[[0, 2, 1], [1, 0, 0], [3, 0, 1]]);
+        assert_eq!(m.determinant(), Q::from(-2));
+    }
+
+    #[test]
+    fn test_claw_machine() {
+        let machine = |ax: i128, ay: i128, bx: i128, by: i128, px: i128, py: i128| {
+            Matrix::<Q>::from_ints(&[[ax, bx], [ay, by]]).solve_integer(&[px, py])
+        };
+        assert_eq!(machine(94, 34, 22, 67, 8400, 5400), Some(vec![80, 40]));
+        assert_eq!(machine(26, 66, 67, 21, 12748, 12176), None);
+        assert_eq!(
+            machine(26, 66, 67, 21, 10000000012748, 10000000012176),
+            Some(vec![118679050709, 103199174542])
+        );
+    }
+
+    #[test]
+    fn test_hailstones() {
+        let hail: [[i128; 6]; 3] = [
+            [19, 13, 30, -2, 1, -2],
+            [18, 19, 22, -1, -1, -2],
+            [20, 25, 34, -2, -2, -4],
+        ];
+        // (P - p) x (V - v) == 0 for every hailstone; differences between pairs
+        // of hailstones cancel the non-linear terms.
+        let mut rows = Vec::new();
+        let mut b = Vec::new();
+        for (i, j) in [(0, 1), (0, 2)] {
+            let (h, k) = (hail[i], hail[j]);
+            for (a, c) in [(0, 1), (0, 2), (1, 2)] {
+                let mut row = [0; 6];
+                row[a] = h[3 + c] - k[3 + c];
+                row[c] = k[3 + a] - h[3 + a];
+                row[3 + a] = k[c] - h[c];
+                row[3 + c] = h[a] - k[a];
+                rows.push(row);
+                b.push((h[a] * h[3 + c] - h[c] * h[3 + a]) - (k[a] * k[3 + c] - k[c] * k[3 + a]));
+            }
+        }
+        let m = Matrix::<Q>::from_ints(&rows);
+        assert_eq!(m.rank(), 6);
+        let b = b.iter().map(|&v| Q::from(v)).collect::<Vec<_>>();
+        let rock = m.solve(&b).unwrap();
+        assert_eq!(m.mul_vec(&rock), b);
+        let rock = rock
+            .iter()
+            .map(|v| v.to_integer().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(rock, vec![24, 13, 10, -3, 1, 2]);
+    }
+}
@@ -0,0 +1,143 @@
+// Linear systems over GF(2), where adding is XOR. Each variable is a toggle
+// (pressed an odd number of times or not) and `columns[j]` is the bitmask of
+// outputs it flips, so variable sets are also bitmasks with up to 64 variables.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XorSolution {
+    particular: u64,
+    null_space: Vec<u64>,
+}
+
+impl XorSolution {
+    // Find every variable set whose columns XOR to `target`.
+    pub fn solve(columns: &[u64], target: u64) -> Option<XorSolution> {
+        assert!(columns.len() <= 64);
+        let outputs = columns.iter().fold(target, |acc, &c| acc | c);
+        // One equation per output bit: the variables touching it, and its target
+        let mut rows = (0..64)
+            .filter(|bit| outputs & (1 << bit) != 0)
+            .map(|bit| {
+                let vars = columns
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &c)| c & (1 << bit) != 0)
+                    .fold(0u64, |vars, (j, _)| vars | (1 << j));
+                (vars, target & (1 << bit) != 0)
+            })
+            .collect::<Vec<_>>();
+
+        let mut pivots = Vec::new();
+        for var in 0..columns.len() {
+            let row = pivots.len();
+            let Some(pivot) = (row..rows.len()).find(|&r| rows[r].0 & (1 << var) != 0) else {
+                continue;
+            };
+            rows.swap(row, pivot);
+            let (vars, rhs) = rows[row];
+            for (i, r) in rows.iter_mut().enumerate() {
+                if i != row && r.0 & (1 << var) != 0 {
+                    *r = (r.0 ^ vars, r.1 ^ rhs);
+                }
+            }
+            pivots.push(var);
+        }
+        if rows[pivots.len()..].iter().any(|&(_, rhs)| rhs) {
+            return None;
+        }
+
+        // Free variables are zero in the particular solution, and each one
+        // gives a null space vector together with the pivots it feeds
+        let particular = pivots
+            .iter()
+            .zip(&rows)
+            .filter(|(_, &(_, rhs))| rhs)
+            .fold(0, |x, (&var, _)| x | (1 << var));
+        let null_space = (0..columns.len())
+            .filter(|var| !pivots.contains(var))
+            .map(|free| {
+                pivots
+                    .iter()
+                    .zip(&rows)
+                    .filter(|(_, &(vars, _))| vars & (1 << free) != 0)
+                    .fold(1 << free, |x, (&var, _)| x | (1 << var))
+            })
+            .collect();
+        Some(XorSolution {
+            particular,
+            null_space,
+        })
+    }
+
+    pub fn particular(&self) -> u64 {
+        self.particular
+    }
+
+    pub fn null_space(&self) -> &[u64] {
+        &self.null_space
+    }
+
+    // Every solution, one per combination of the null space. Counted in u128
+    // since there can be 2^64 of them.
+    pub fn solutions(&self) -> impl Iterator<Item = u64> + '_ {
+        (0..1u128 << self.null_space.len()).map(|combo| {
+            self.null_space
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| combo & (1 << i) != 0)
+                .fold(self.particular, |x, (_, &v)| x ^ v)
+        })
+    }
+
+    // The solution toggling the fewest variables
+    pub fn min_weight(&self) -> u64 {
+        self.solutions().min_by_key(|x| x.count_ones()).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mask(bits: &[usize]) -> u64 {
+        bits.iter().fold(0, |m, b| m | (1 << b))
+    }
+
+    #[test]
+    fn test_lights() {
+        let buttons = [
+            mask(&[3]),
+            mask(&[1, 3]),
+            mask(&[2]),
+            mask(&[2, 3]),
+            mask(&[0, 2]),
+            mask(&[0, 1]),
+        ];
+        let solution = XorSolution::solve(&buttons, mask(&[1, 2])).unwrap();
+        assert_eq!(solution.null_space().len(), 2);
+        assert_eq!(solution.solutions().count(), 4);
+        for x in solution.solutions() {
+            let lights = (0..buttons.len())
+                .filter(|j| x & (1 << j) != 0)
+                .fold(0, |l, j| l ^ buttons[j]);
+            assert_eq!(lights, mask(&[1, 2]));
+        }
+        assert_eq!(solution.min_weight().count_ones(), 2);
+
+        // Every variable free
+        let solution = XorSolution::solve(&[0; 64], 0).unwrap();
+        assert_eq!(solution.null_space().len(), 64);
+        assert_eq!(
+            solution.solutions().take(4).collect::<Vec<_>>(),
+            [0, 1, 2, 3]
+        );
+
+        let buttons = [
+            mask(&[0, 1, 2, 3, 4]),
+            mask(&[0, 3, 4]),
+            mask(&[0, 1, 2, 4, 5]),
+            mask(&[1, 2]),
+        ];
+        let solution = XorSolution::solve(&buttons, mask(&[1, 2, 3, 5])).unwrap();
+        assert_eq!(solution.min_weight(), mask(&[1, 2]));
+        assert_eq!(XorSolution::solve(&[mask(&[0, 1])], mask(&[0])), None);
+    }
+}
@@ -0,0 +1,373 @@
+use num_traits::{One, Zero};
+
+use super::rational::Rational;
+
+type Q = Rational<i128>;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Relation {
+    Le,
+    Eq,
+    Ge,
+}
+
+impl Relation {
+    fn flip(self) -> Relation {
+        match self {
+            Relation::Le => Relation::Ge,
+            Relation::Eq => Relation::Eq,
+            Relation::Ge => Relation::Le,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Error {
+    Infeasible,
+    Unbounded,
+}
+
+#[derive(Debug, Clone)]
+struct Constraint {
+    coeffs: Vec<i64>,
+    rel: Relation,
+    rhs: i64,
+}
+
+// Optimise objective . x subject to linear constraints, with every x >= 0.
+// The LP relaxation is solved exactly with a two phase simplex, and integer
+// solutions are found by branch and bound on top of it.
+#[derive(Debug, Clone)]
+pub struct IntegerProgram {
+    objective: Vec<i64>,
+    maximise: bool,
+    constraints: Vec<Constraint>,
+}
+
+// A dense simplex tableau; each row ends with its right hand side.
+struct Tableau {
+    rows: Vec<Vec<Q>>,
+    basis: Vec<usize>,
+}
+
+impl Tableau {
+    fn rhs(&self, row: usize) -> Q {
+        *self.rows[row].last().unwrap()
+    }
+
+    fn pivot(&mut self, row: usize, col: usize) {
+        let p = self.rows[row][col];
+        self.rows[row].iter_mut().for_each(|v| *v /= p);
+        let pivot_row = self.rows[row].clone();
+        for (i, r) in self.rows.iter_mut().enumerate() {
+            let factor = r[col];
+            if i != row && !factor.is_zero() {
+                r.iter_mut()
+                    .zip(&pivot_row)
+                    .for_each(|(v, &p)| *v -= factor * p);
+            }
+        }
+        self.basis[row] = col;
+    }
+
+    // Minimise cost . x using only the first `usable` columns, with Bland's
+    // rule so degenerate problems can't cycle.
+    fn optimise(&mut self, cost: &[Q], usable: usize) -> Result<(), Error> {
+        loop {
+            let reduced = |j: usize| {
+                self.rows
+                    .iter()
+                    .zip(&self.basis)
+                    .fold(cost[j], |r, (row, &b)| r - cost[b] * row[j])
+            };
+            let Some(col) = (0..usable).find(|&j| reduced(j) < Q::zero()) else {
+                return Ok(());
+            };
+            let row = (0..self.rows.len())
+                .filter(|&i| self.rows[i][col] > Q::zero())
+                .min_by(|&a, &b| {
+                    let ra = self.rhs(a) / self.rows[a][col];
+                    let rb = self.rhs(b) / self.rows[b][col];
+                    ra.cmp(&rb).then(self.basis[a].cmp(&self.basis[b]))
+                })
+                .ok_or(Error::Unbounded)?;
+            self.pivot(row, col);
+        }
+    }
+}
+
+// Minimise cost . x over x >= 0 subject to the constraints.
+fn simplex<'a>(
+    cost: &[Q],
+    constraints: impl Iterator<Item = &'a Constraint>,
+) -> Result<(Q, Vec<Q>), Error> {
+    let vars = cost.len();
+    // Make every right hand side non-negative
+    let constraints = constraints
+        .map(|c| {
+            if c.rhs < 0 {
+                Constraint {
+                    coeffs: c.coeffs.iter().map(|&v| -v).collect(),
+                    rel: c.rel.flip(),
+                    rhs: -c.rhs,
+                }
+            } else {
+                c.clone()
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // Columns are the variables, then slacks, then artificials
+    let slacks = constraints.iter().filter(|c| c.rel != Relation::Eq).count();
+    let artificials = constraints.iter().filter(|c| c.rel != Relation::Le).count();
+    let art_start = vars + slacks;
+    let cols = art_start + artificials;
+
+    let mut tableau = Tableau {
+        rows: Vec::new(),
+        basis: Vec::new(),
+    };
+    let (mut slack, mut art) = (vars, art_start);
+    for c in &constraints {
+        let mut row = vec![Q::zero(); cols + 1];
+        for (v, &coeff) in row.iter_mut().zip(&c.coeffs) {
+            *v = Q::from(coeff as i128);
+        }
+        row[cols] = Q::from(c.rhs as i128);
+        match c.rel {
+            Relation::Le => {
+                row[slack] = Q::one();
+                tableau.basis.push(slack);
+                slack += 1;
+            }
+            Relation::Ge => {
+                row[slack] = -Q::one();
+                slack += 1;
+                row[art] = Q::one();
+                tableau.basis.push(art);
+                art += 1;
+            }
+            Relation::Eq => {
+                row[art] = Q::one();
+                tableau.basis.push(art);
+                art += 1;
+            }
+        }
+        tableau.rows.push(row);
+    }
+
+    // Phase 1: drive the artificials to zero
+    let mut phase1 = vec![Q::zero(); cols];
+    phase1[art_start..].iter_mut().for_each(|v| *v = Q::one());
+    tableau.optimise(&phase1, cols)?;
+    let infeasible = (0..tableau.rows.len())
+        .any(|i| tableau.basis[i] >= art_start && tableau.rhs(i) > Q::zero());
+    if infeasible {
+        return Err(Error::Infeasible);
+    }
+    // Pivot any remaining (zero valued) artificials out of the basis, or drop
+    // their rows if they are redundant
+    let mut i = 0;
+    while i < tableau.rows.len() {
+        if tableau.basis[i] >= art_start {
+            match (0..art_start).find(|&j| !tableau.rows[i][j].is_zero()) {
+                Some(j) => tableau.pivot(i, j),
+                None => {
+                    tableau.rows.remove(i);
+                    tableau.basis.remove(i);
+                    continue;
+                }
+            }
+        }
+        i += 1;
+    }
+
+    // Phase 2: the real objective, never letting an artificial back in
+    let mut phase2 = cost.to_vec();
+    phase2.resize(cols, Q::zero());
+    tableau.optimise(&phase2, art_start)?;
+
+    let mut x = vec![Q::zero(); vars];
+    for (i, &b) in tableau.basis.iter().enumerate() {
+        if b < vars {
+            x[b] = tableau.rhs(i);
+        }
+    }
+    let value = cost
+        .iter()
+        .zip(&x)
+        .fold(Q::zero(), |sum, (&c, &v)| sum + c * v);
+    Ok((value, x))
+}
+
+impl IntegerProgram {
+    pub fn minimise(objective: &[i64]) -> IntegerProgram {
+        IntegerProgram {
+            objective: objective.to_vec(),
+            maximise: false,
+            constraints: Vec::new(),
+        }
+    }
+
+    pub fn maximise(objective: &[i64]) -> IntegerProgram {
+        IntegerProgram {
+            maximise: true,
+            ..IntegerProgram::minimise(objective)
+        }
+    }
+
+    pub fn vars(&self) -> usize {
+        self.objective.len()
+    }
+
+    pub fn constrain(&mut self, coeffs: &[i64], rel: Relation, rhs: i64) -> &mut Self {
+        assert_eq!(coeffs.len(), self.vars());
+        self.constraints.push(Constraint {
+            coeffs: coeffs.to_vec(),
+            rel,
+            rhs,
+        });
+        self
+    }
+
+    fn sign(&self) -> i64 {
+        if self.maximise {
+            -1
+        } else {
+            1
+        }
+    }
+
+    // The relaxation as a minimisation, with extra branching bounds
+    fn relax(&self, bounds: &[Constraint]) -> Result<(Q, Vec<Q>), Error> {
+        let cost = self
+            .objective
+            .iter()
+            .map(|&c| Q::from((c * self.sign()) as i128))
+            .collect::<Vec<_>>();
+        simplex(&cost, self.constraints.iter().chain(bounds))
+    }
+
+    // The optimum without the integer restriction
+    pub fn solve_relaxation(&self) -> Result<(Q, Vec<Q>), Error> {
+        let (value, x) = self.relax(&[])?;
+        Ok((value * Q::from(self.sign() as i128), x))
+    }
+
+    fn branch(
+        &self,
+        bounds: &mut Vec<Constraint>,
+        best: &mut Option<(i64, Vec<i64>)>,
+    ) -> Result<(), Error> {
+        let (value, x) = match self.relax(bounds) {
+            Err(Error::Infeasible) => return Ok(()),
+            result => result?,
+        };
+        // The objective is integral, so this branch can only help if it can
+        // beat the best by at least one
+        if best
+            .as_ref()
+            .is_some_and(|(b, _)| value.ceil() >= *b as i128)
+        {
+            return Ok(());
+        }
+        match x.iter().position(|v| !v.is_integer()) {
+            None => {
+                let x = x.iter().map(|v| v.num() as i64).collect();
+                *best = Some((value.num() as i64, x));
+            }
+            Some(j) => {
+                let mut coeffs = vec![0; self.vars()];
+                coeffs[j] = 1;
+                let split = [
+                    (Relation::Le, x[j].floor() as i64),
+                    (Relation::Ge, x[j].ceil() as i64),
+                ];
+                for (rel, rhs) in split {
+                    bounds.push(Constraint {
+                        coeffs: coeffs.clone(),
+                        rel,
+                        rhs,
+                    });
+                    self.branch(bounds, best)?;
+                    bounds.pop();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // The optimal value and an integer x achieving it
+    pub fn solve(&self) -> Result<(i64, Vec<i64>), Error> {
+        let mut best = None;
+        self.branch(&mut Vec::new(), &mut best)?;
+        let (value, x) = best.ok_or(Error::Infeasible)?;
+        Ok((value * self.sign(), x))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn presses(buttons: &[&[usize]], joltage: &[i64]) -> i64 {
+        let mut ip = IntegerProgram::minimise(&vec![1; buttons.len()]);
+        for (i, &j) in joltage.iter().enumerate() {
+            let coeffs = buttons
+                .iter()
+                .map(|b| b.contains(&i) as i64)
+                .collect::<Vec<_>>();
+            ip.constrain(&coeffs, Relation::Eq, j);
+        }
+        let (value, x) = ip.solve().unwrap();
+        assert_eq!(x.iter().sum::<i64>(), value);
+        value
+    }
+
+    #[test]
+    fn test_button_presses() {
+        assert_eq!(
+            presses(
+                &[&[3], &[1, 3], &[2], &[2, 3], &[0, 2], &[0, 1]],
+                &[3, 5, 4, 7]
+            ),
+            10
+        );
+        assert_eq!(
+            presses(
+                &[&[0, 2, 3, 4], &[2, 3], &[0, 4], &[0, 1, 2], &[1, 2, 3, 4]],
+                &[7, 5, 12, 7, 2]
+            ),
+            12
+        );
+        assert_eq!(
+            presses(
+                &[&[0, 1, 2, 3, 4], &[0, 3, 4], &[0, 1, 2, 4, 5], &[1, 2]],
+                &[10, 11, 11, 5, 10, 5]
+            ),
+            11
+        );
+    }
+
+    #[test]
+    fn test_integer_program() {
+        let mut ip = IntegerProgram::maximise(&[1, 1]);
+        ip.constrain(&[2, 2], Relation::Le, 3);
+        assert_eq!(ip.solve_relaxation().unwrap().0, Q::new(3, 2));
+        assert_eq!(ip.solve().unwrap().0, 1);
+
+        let mut ip = IntegerProgram::maximise(&[5, 4]);
+        ip.constrain(&[6, 4], Relation::Le, 24)
+            .constrain(&[1, 2], Relation::Le, 6)
+            .constrain(&[1, 1], Relation::Ge, 1);
+        assert_eq!(ip.solve_relaxation().unwrap().0, Q::from(21));
+        assert_eq!(ip.solve(), Ok((20, vec![4, 0])));
+
+        let mut ip = IntegerProgram::minimise(&[1]);
+        ip.constrain(&[2], Relation::Eq, 3);
+        assert_eq!(ip.solve(), Err(Error::Infeasible));
+        let mut ip = IntegerProgram::maximise(&[1, 0]);
+        ip.constrain(&[1, -1], Relation::Le, 2);
+        assert_eq!(ip.solve(), Err(Error::Unbounded));
+    }
+}
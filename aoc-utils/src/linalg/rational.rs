@@ -0,0 +1,233 @@
+use std::{
+    cmp::Ordering,
+    fmt::Display,
+    ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign},
+};
+
+use num_traits::{One, PrimInt, Signed, Zero};
+
+use crate::math::gcd;
+
+// An exact fraction, always stored in lowest terms with a positive denominator.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Rational<T: PrimInt + Signed> {
+    num: T,
+    den: T,
+}
+
+impl<T: PrimInt + Signed> Rational<T> {
+    pub fn new(num: T, den: T) -> Rational<T> {
+        assert!(!den.is_zero(), "Rational with zero denominator");
+        let g = gcd(num, den);
+        let (num, den) = (num / g, den / g);
+        if den < T::zero() {
+            Rational {
+                num: -num,
+                den: -den,
+            }
+        } else {
+            Rational { num, den }
+        }
+    }
+
+    pub fn from_integer(v: T) -> Rational<T> {
+        Rational {
+            num: v,
+            den: T::one(),
+        }
+    }
+
+    pub fn num(&self) -> T {
+        self.num
+    }
+
+    pub fn den(&self) -> T {
+        self.den
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.den.is_one()
+    }
+
+    pub fn to_integer(&self) -> Option<T> {
+        if self.is_integer() {
+            Some(self.num)
+        } else {
+            None
+        }
+    }
+
+    pub fn floor(&self) -> T {
+        let q = self.num / self.den;
+        if self.num < T::zero() && !(self.num % self.den).is_zero() {
+            q - T::one()
+        } else {
+            q
+        }
+    }
+
+    pub fn ceil(&self) -> T {
+        -(-*self).floor()
+    }
+
+    pub fn abs(&self) -> Self {
+        Rational {
+            num: self.num.abs(),
+            den: self.den,
+        }
+    }
+
+    pub fn recip(&self) -> Self {
+        Rational::new(self.den, self.num)
+    }
+
+    pub fn to_f64(&self) -> Option<f64> {
+        Some(self.num.to_f64()? / self.den.to_f64()?)
+    }
+}
+
+impl<T: PrimInt + Signed + Display> Display for Rational<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_integer() {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+impl<T: PrimInt + Signed> From<T> for Rational<T> {
+    fn from(v: T) -> Self {
+        Rational::from_integer(v)
+    }
+}
+
+impl<T: PrimInt + Signed> PartialOrd for Rational<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: PrimInt + Signed> Ord for Rational<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.num * other.den).cmp(&(other.num * self.den))
+    }
+}
+
+impl<T: PrimInt + Signed> Add for Rational<T> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        let g = gcd(self.den, rhs.den);
+        Rational::new(
+            self.num * (rhs.den / g) + rhs.num * (self.den / g),
+            self.den / g * rhs.den,
+        )
+    }
+}
+
+impl<T: PrimInt + Signed> Sub for Rational<T> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self + (-rhs)
+    }
+}
+
+impl<T: PrimInt + Signed> Neg for Rational<T> {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Rational {
+            num: -self.num,
+            den: self.den,
+        }
+    }
+}
+
+impl<T: PrimInt + Signed> Mul for Rational<T> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        // Cross reduce first to keep the intermediate values small
+        let g1 = gcd(self.num, rhs.den);
+        let g2 = gcd(rhs.num, self.den);
+        Rational::new(
+            (self.num / g1) * (rhs.num / g2),
+            (self.den / g2) * (rhs.den / g1),
+        )
+    }
+}
+
+impl<T: PrimInt + Signed> Div for Rational<T> {
+    type Output = Self;
+
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn div(self, rhs: Self) -> Self::Output {
+        self * rhs.recip()
+    }
+}
+
+impl<T: PrimInt + Signed> AddAssign for Rational<T> {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl<T: PrimInt + Signed> SubAssign for Rational<T> {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl<T: PrimInt + Signed> MulAssign for Rational<T> {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
+impl<T: PrimInt + Signed> DivAssign for Rational<T> {
+    fn div_assign(&mut self, rhs: Self) {
+        *self = *self / rhs;
+    }
+}
+
+impl<T: PrimInt + Signed> Zero for Rational<T> {
+    fn zero() -> Self {
+        Rational::from_integer(T::zero())
+    }
+
+    fn is_zero(&self) -> bool {
+        self.num.is_zero()
+    }
+}
+
+impl<T: PrimInt + Signed> One for Rational<T> {
+    fn one() -> Self {
+        Rational::from_integer(T::one())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Q = Rational<i128>;
+
+    #[test]
+    fn test_rational() {
+        let a = Q::new(6, -8);
+        assert_eq!((a.num(), a.den()), (-3, 4));
+        assert_eq!(a.to_string(), "-3/4");
+        let b = Q::new(1, 6);
+        assert_eq!(a + b, Q::new(-7, 12));
+        assert_eq!(a - b, Q::new(-11, 12));
+        assert_eq!(a * b, Q::new(-1, 8));
+        assert_eq!(a / b, Q::new(-9, 2));
+        assert!(a < b);
+        assert_eq!(a.floor(), -1);
+        assert_eq!(a.ceil(), 0);
+        assert_eq!(Q::new(8, 4).to_integer(), Some(2));
+        assert_eq!(Q::zero() * a, Q::zero());
+    }
+}
@@ -0,0 +1,347 @@
+use std::{
+    fmt::Display,
+    ops::{Add, AddAssign, Div, Mul, MulAssign, Neg, Sub, SubAssign},
+};
+
+use num_traits::{PrimInt, Signed};
+
+fn abs<T: PrimInt>(v: T) -> T {
+    if v < T::zero() {
+        T::zero() - v
+    } else {
+        v
+    }
+}
+
+pub fn gcd<T: PrimInt>(a: T, b: T) -> T {
+    let (mut a, mut b) = (abs(a), abs(b));
+    while !b.is_zero() {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+pub fn lcm<T: PrimInt>(a: T, b: T) -> T {
+    if a.is_zero() || b.is_zero() {
+        T::zero()
+    } else {
+        abs(a / gcd(a, b) * b)
+    }
+}
+
+pub fn gcd_all<T: PrimInt>(values: impl IntoIterator<Item = T>) -> T {
+    values.into_iter().fold(T::zero(), gcd)
+}
+
+pub fn lcm_all<T: PrimInt>(values: impl IntoIterator<Item = T>) -> T {
+    values.into_iter().fold(T::one(), lcm)
+}
+
+// Returns (g, x, y) such that a * x + b * y == g == gcd(a, b)
+pub fn extended_gcd<T: PrimInt + Signed>(a: T, b: T) -> (T, T, T) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_s, mut s) = (T::one(), T::zero());
+    let (mut old_t, mut t) = (T::zero(), T::one());
+    while !r.is_zero() {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_s, s) = (s, old_s - q * s);
+        (old_t, t) = (t, old_t - q * t);
+    }
+    if old_r < T::zero() {
+        (-old_r, -old_s, -old_t)
+    } else {
+        (old_r, old_s, old_t)
+    }
+}
+
+fn rem_euclid<T: PrimInt + Signed>(a: T, m: T) -> T {
+    ((a % m) + m) % m
+}
+
+// The x in [0, m) such that a * x == 1 (mod m)
+pub fn mod_inverse<T: PrimInt + Signed>(a: T, m: T) -> Option<T> {
+    let (g, x, _) = extended_gcd(rem_euclid(a, m), m);
+    if g.is_one() {
+        Some(rem_euclid(x, m))
+    } else {
+        None
+    }
+}
+
+pub fn mod_mul(a: u64, b: u64, m: u64) -> u64 {
+    ((a as u128 * b as u128) % m as u128) as u64
+}
+
+pub fn mod_pow(base: u64, exp: u64, m: u64) -> u64 {
+    let mut result = 1 % m;
+    let mut base = base % m;
+    let mut exp = exp;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mod_mul(result, base, m);
+        }
+        base = mod_mul(base, base, m);
+        exp >>= 1;
+    }
+    result
+}
+
+// Chinese Remainder Theorem for (residue, modulus) pairs, allowing moduli that
+// share factors. Returns (x, lcm of moduli) with x the smallest non-negative
+// solution, or None if the congruences contradict each other, a modulus isn't
+// positive or the lcm overflows.
+pub fn crt(congruences: impl IntoIterator<Item = (i64, i64)>) -> Option<(i64, i64)> {
+    let (x, m) = congruences
+        .into_iter()
+        .try_fold((0i128, 1i128), |(x, m), (r, n)| {
+            if n <= 0 {
+                return None;
+            }
+            let (r, n) = (r as i128, n as i128);
+            let (g, p, _) = extended_gcd(m, n);
+            let diff = r - x;
+            if diff % g != 0 {
+                return None;
+            }
+            let l = (m / g).checked_mul(n)?;
+            let k = (diff / g % (n / g) * p % (n / g)).rem_euclid(n / g);
+            Some(((x + m * k).rem_euclid(l), l))
+        })?;
+    Some((i64::try_from(x).ok()?, i64::try_from(m).ok()?))
+}
+
+// floor(sqrt(n))
+pub fn isqrt(n: u64) -> u64 {
+    if n < 2 {
+        return n;
+    }
+    let mut x = (n as f64).sqrt() as u64;
+    while x.checked_mul(x).is_none_or(|sq| sq > n) {
+        x -= 1;
+    }
+    while (x + 1).checked_mul(x + 1).is_some_and(|sq| sq <= n) {
+        x += 1;
+    }
+    x
+}
+
+pub fn is_square(n: u64) -> bool {
+    let r = isqrt(n);
+    r * r == n
+}
+
+// Prime factors with their multiplicity, in increasing order.
+pub fn factorise(n: u64) -> Vec<(u64, u32)> {
+    let mut factors = Vec::new();
+    let mut n = n;
+    let mut p = 2;
+    while p <= n / p {
+        let mut count = 0;
+        while n.is_multiple_of(p) {
+            n /= p;
+            count += 1;
+        }
+        if count > 0 {
+            factors.push((p, count));
+        }
+        p += if p == 2 { 1 } else { 2 };
+    }
+    if n > 1 {
+        factors.push((n, 1));
+    }
+    factors
+}
+
+pub fn divisors(n: u64) -> Vec<u64> {
+    let mut divisors = factorise(n).iter().fold(vec![1], |divs, &(p, count)| {
+        divs.iter()
+            .flat_map(|d| (0..=count).map(move |e| d * p.pow(e)))
+            .collect()
+    });
+    divisors.sort();
+    divisors
+}
+
+// An integer modulo M. Division requires M to be prime.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct ModInt<const M: u64>(u64);
+
+impl<const M: u64> ModInt<M> {
+    pub fn new(v: u64) -> ModInt<M> {
+        ModInt(v % M)
+    }
+
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+
+    pub fn pow(&self, exp: u64) -> Self {
+        ModInt(mod_pow(self.0, exp, M))
+    }
+
+    pub fn inverse(&self) -> Option<Self> {
+        let inv = mod_inverse(self.0 as i128, M as i128)?;
+        Some(ModInt(inv as u64))
+    }
+}
+
+impl<const M: u64> Display for ModInt<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl<const M: u64> From<u64> for ModInt<M> {
+    fn from(v: u64) -> Self {
+        ModInt::new(v)
+    }
+}
+
+impl<const M: u64> From<i64> for ModInt<M> {
+    fn from(v: i64) -> Self {
+        ModInt((v as i128).rem_euclid(M as i128) as u64)
+    }
+}
+
+impl<const M: u64> Add for ModInt<M> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        ModInt(((self.0 as u128 + rhs.0 as u128) % M as u128) as u64)
+    }
+}
+
+impl<const M: u64> Sub for ModInt<M> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self + (-rhs)
+    }
+}
+
+impl<const M: u64> Neg for ModInt<M> {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        ModInt((M - self.0) % M)
+    }
+}
+
+impl<const M: u64> Mul for ModInt<M> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        ModInt(mod_mul(self.0, rhs.0, M))
+    }
+}
+
+impl<const M: u64> Div for ModInt<M> {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        let inv = rhs.inverse().expect("No inverse for divisor");
+        ModInt(mod_mul(self.0, inv.0, M))
+    }
+}
+
+impl<const M: u64> AddAssign for ModInt<M> {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl<const M: u64> SubAssign for ModInt<M> {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl<const M: u64> MulAssign for ModInt<M> {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gcd_lcm() {
+        assert_eq!(gcd(12, 18), 6);
+        assert_eq!(gcd(-12i64, 18), 6);
+        assert_eq!(gcd(0u32, 7), 7);
+        assert_eq!(lcm(4, 6), 12);
+        assert_eq!(lcm_all([2u64, 3, 4, 5]), 60);
+        assert_eq!(gcd_all([12, 18, 30]), 6);
+        assert_eq!(lcm_all([3739u64, 3761, 3797, 3889]), 207_652_583_562_007);
+    }
+
+    #[test]
+    fn test_extended_gcd() {
+        let (g, x, y) = extended_gcd(240i64, 46);
+        assert_eq!(g, 2);
+        assert_eq!(240 * x + 46 * y, 2);
+        assert_eq!(mod_inverse(3i64, 11), Some(4));
+        assert_eq!(mod_inverse(-3i64, 11), Some(7));
+        assert_eq!(mod_inverse(4i64, 8), None);
+    }
+
+    #[test]
+    fn test_mod_pow() {
+        assert_eq!(mod_pow(2, 10, 1000), 24);
+        assert_eq!(mod_pow(3, 0, 1), 0);
+        assert_eq!(mod_pow(u64::MAX - 1, u64::MAX, u64::MAX), u64::MAX - 1);
+    }
+
+    #[test]
+    fn test_crt() {
+        assert_eq!(crt([(2, 3), (3, 5), (2, 7)]), Some((23, 105)));
+        assert_eq!(crt([(2, 4), (4, 6)]), Some((10, 12)));
+        assert_eq!(crt([(1, 4), (2, 6)]), None);
+        assert_eq!(crt([(1, 4), (0, 0)]), None);
+        assert_eq!(crt([(1, 4), (0, -3)]), None);
+        // Pairwise coprime moduli whose product is past i128
+        let primes = [
+            1_000_000_007i64,
+            1_000_000_009,
+            998_244_353,
+            1_000_000_021,
+            1_000_000_033,
+        ];
+        assert_eq!(crt(primes.map(|p| (1, p))), None);
+        // Bus schedule: 17,x,13,19 => t = 0 mod 17, t = -2 mod 13, t = -3 mod 19
+        assert_eq!(crt([(0, 17), (-2, 13), (-3, 19)]), Some((3417, 4199)));
+    }
+
+    #[test]
+    fn test_isqrt_factorise() {
+        assert_eq!(isqrt(0), 0);
+        assert_eq!(isqrt(15), 3);
+        assert_eq!(isqrt(16), 4);
+        assert_eq!(isqrt(u64::MAX), 4294967295);
+        assert!(is_square(1 << 40));
+        assert_eq!(factorise(360), vec![(2, 3), (3, 2), (5, 1)]);
+        assert_eq!(factorise(9973), vec![(9973, 1)]);
+        assert_eq!(divisors(12), vec![1, 2, 3, 4, 6, 12]);
+    }
+
+    #[test]
+    fn test_mod_int() {
+        type M7 = ModInt<7>;
+        let a = M7::new(5);
+        let b = M7::from(-4i64);
+        assert_eq!(b.value(), 3);
+        assert_eq!((a + b).value(), 1);
+        assert_eq!((a - b).value(), 2);
+        assert_eq!((b - a).value(), 5);
+        assert_eq!((a * b).value(), 1);
+        assert_eq!((a / b) * b, a);
+        assert_eq!(a.pow(6).value(), 1);
+        let mut c = a;
+        c *= a;
+        c += M7::new(1);
+        assert_eq!(c.to_string(), "5");
+    }
+}
@@ -0,0 +1,216 @@
+// Caching for recursive functions. A Memo is passed to the function being
+// memoised, which looks up its sub-problems through it, and a table fills in
+// integer-indexed sub-problems from the bottom up.
+use std::{collections::HashMap, fmt::Display, hash::Hash};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct Stats {
+    pub hits: usize,
+    pub misses: usize,
+}
+
+impl Stats {
+    pub fn calls(&self) -> usize {
+        self.hits + self.misses
+    }
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} calls, {} hits, {} misses",
+            self.calls(),
+            self.hits,
+            self.misses
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Memo<K, V> {
+    cache: HashMap<K, V>,
+    stats: Stats,
+}
+
+impl<K: Hash + Eq, V: Clone> Default for Memo<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Hash + Eq, V: Clone> Memo<K, V> {
+    pub fn new() -> Memo<K, V> {
+        Memo {
+            cache: HashMap::new(),
+            stats: Stats::default(),
+        }
+    }
+
+    // The cached value for key, or else compute it. The computation gets the
+    // memo back so it can recurse through it.
+    pub fn get_or_compute(&mut self, key: K, compute: impl FnOnce(&mut Self) -> V) -> V {
+        if let Some(value) = self.cache.get(&key) {
+            self.stats.hits += 1;
+            return value.clone();
+        }
+        self.stats.misses += 1;
+        let value = compute(self);
+        self.cache.insert(key, value.clone());
+        value
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.cache.get(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    // Forget everything, for when the context the values depend on changes,
+    // like the next line of input.
+    pub fn reset(&mut self) {
+        self.cache.clear();
+        self.stats = Stats::default();
+    }
+}
+
+// Fill in values 0..n in order, each computed from those before it.
+pub fn table<V>(n: usize, mut compute: impl FnMut(&[V], usize) -> V) -> Vec<V> {
+    let mut values = Vec::with_capacity(n);
+    for i in 0..n {
+        let value = compute(&values, i);
+        values.push(value);
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Ways to fit groups of damaged springs ('#') into the pattern, where '?'
+    // may be either.
+    fn arrangements(memo: &mut Memo<(usize, usize), u64>, springs: &[u8], groups: &[usize]) -> u64 {
+        let key = (springs.len(), groups.len());
+        memo.get_or_compute(key, |memo| {
+            let Some(&first) = springs.first() else {
+                return groups.is_empty() as u64;
+            };
+            let mut ways = 0;
+            if first != b'#' {
+                ways += arrangements(memo, &springs[1..], groups);
+            }
+            if let Some((&group, rest)) = groups.split_first() {
+                let fits = springs.len() >= group
+                    && springs[..group].iter().all(|&s| s != b'.')
+                    && springs.get(group) != Some(&b'#');
+                if first != b'.' && fits {
+                    let after = (group + 1).min(springs.len());
+                    ways += arrangements(memo, &springs[after..], rest);
+                }
+            }
+            ways
+        })
+    }
+
+    #[test]
+    fn test_springs() {
+        let input = "???.### 1,1,3
+.??..??...?##. 1,1,3
+?#?#?#?#?#?#?#? 1,3,1,6
+????.#...#... 4,1,1
+????.######..#####. 1,6,5
+?###???????? 3,2,1";
+        let mut memo = Memo::new();
+        let mut total = |copies: usize| {
+            input
+                .lines()
+                .map(|line| {
+                    let (springs, groups) = line.split_once(' ').unwrap();
+                    let springs = vec![springs; copies].join("?");
+                    let groups = vec![groups; copies].join(",");
+                    let groups = groups
+                        .split(',')
+                        .map(|g| g.parse().unwrap())
+                        .collect::<Vec<_>>();
+                    memo.reset();
+                    arrangements(&mut memo, springs.as_bytes(), &groups)
+                })
+                .sum::<u64>()
+        };
+        assert_eq!(total(1), 21);
+        assert_eq!(total(5), 525152);
+    }
+
+    fn stones(memo: &mut Memo<(u64, usize), u64>, stone: u64, blinks: usize) -> u64 {
+        if blinks == 0 {
+            return 1;
+        }
+        memo.get_or_compute((stone, blinks), |memo| {
+            let digits = stone.checked_ilog10().unwrap_or(0) + 1;
+            if stone == 0 {
+                stones(memo, 1, blinks - 1)
+            } else if digits.is_multiple_of(2) {
+                let half = 10u64.pow(digits / 2);
+                stones(memo, stone / half, blinks - 1) + stones(memo, stone % half, blinks - 1)
+            } else {
+                stones(memo, stone * 2024, blinks - 1)
+            }
+        })
+    }
+
+    #[test]
+    fn test_stones() {
+        let mut memo = Memo::new();
+        assert_eq!(stones(&mut memo, 125, 6) + stones(&mut memo, 17, 6), 22);
+        let stats = memo.stats();
+        assert!(stats.hits > 0);
+        assert_eq!(stats.misses, memo.len());
+        let total = [125, 17]
+            .map(|s| stones(&mut memo, s, 25))
+            .iter()
+            .sum::<u64>();
+        assert_eq!(total, 55312);
+        assert!(memo.stats().calls() > stats.calls());
+        memo.reset();
+        assert!(memo.is_empty());
+        assert_eq!(memo.stats(), Stats::default());
+    }
+
+    #[test]
+    fn test_table() {
+        let towels = ["r", "wr", "b", "g", "bwu", "rb", "gb", "br"];
+        let designs = [
+            "brwrr", "bggr", "gbbr", "rrbgbr", "ubwu", "bwurrg", "brgr", "bbrgwb",
+        ];
+        // Ways to make each suffix of the design, from the shortest up
+        let ways = |design: &str| {
+            let n = design.len();
+            let suffixes = table(n + 1, |shorter: &[u64], len| {
+                if len == 0 {
+                    return 1;
+                }
+                let suffix = &design[n - len..];
+                towels
+                    .iter()
+                    .filter(|t| suffix.starts_with(*t))
+                    .map(|t| shorter[len - t.len()])
+                    .sum()
+            });
+            suffixes[n]
+        };
+        let ways = designs.map(ways);
+        assert_eq!(ways.iter().filter(|&&w| w > 0).count(), 6);
+        assert_eq!(ways.iter().sum::<u64>(), 16);
+    }
+}
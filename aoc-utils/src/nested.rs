@@ -0,0 +1,352 @@
+use std::{cmp::Ordering, fmt::Display, iter::Peekable, ops::Add, str::FromStr};
+
+use num_traits::Zero;
+
+// A value or an arbitrarily nested list of them, written like "[1,[2,3],[]]".
+#[derive(Debug, Clone)]
+pub enum NestedList<T> {
+    Value(T),
+    List(Vec<NestedList<T>>),
+}
+
+impl<T> NestedList<T> {
+    pub fn is_value(&self) -> bool {
+        matches!(self, NestedList::Value(_))
+    }
+
+    pub fn value(&self) -> Option<&T> {
+        match self {
+            NestedList::Value(v) => Some(v),
+            NestedList::List(_) => None,
+        }
+    }
+
+    pub fn items(&self) -> Option<&[NestedList<T>]> {
+        match self {
+            NestedList::Value(_) => None,
+            NestedList::List(items) => Some(items),
+        }
+    }
+
+    // The deepest nesting of lists; a bare value has depth 0.
+    pub fn depth(&self) -> usize {
+        match self {
+            NestedList::Value(_) => 0,
+            NestedList::List(items) => 1 + items.iter().map(|i| i.depth()).max().unwrap_or(0),
+        }
+    }
+
+    // All the values, depth first from left to right.
+    pub fn flatten(&self) -> Vec<&T> {
+        match self {
+            NestedList::Value(v) => vec![v],
+            NestedList::List(items) => items.iter().flat_map(|i| i.flatten()).collect(),
+        }
+    }
+
+    pub fn flatten_mut(&mut self) -> Vec<&mut T> {
+        match self {
+            NestedList::Value(v) => vec![v],
+            NestedList::List(items) => items.iter_mut().flat_map(|i| i.flatten_mut()).collect(),
+        }
+    }
+
+    // The node reached by following the indices of a path from here.
+    pub fn get(&self, path: &[usize]) -> Option<&NestedList<T>> {
+        path.iter().try_fold(self, |node, &i| node.items()?.get(i))
+    }
+
+    pub fn get_mut(&mut self, path: &[usize]) -> Option<&mut NestedList<T>> {
+        path.iter().try_fold(self, |node, &i| match node {
+            NestedList::Value(_) => None,
+            NestedList::List(items) => items.get_mut(i),
+        })
+    }
+
+    // Depth first search for the first node matching pred, which is given the
+    // node and its depth. Returns the path to it, and the number of values
+    // that come before it.
+    pub fn find(
+        &self,
+        pred: &impl Fn(&NestedList<T>, usize) -> bool,
+    ) -> Option<(Vec<usize>, usize)> {
+        let mut path = Vec::new();
+        let mut before = 0;
+        self.find_from(pred, &mut path, &mut before)
+            .then_some((path, before))
+    }
+
+    fn find_from(
+        &self,
+        pred: &impl Fn(&NestedList<T>, usize) -> bool,
+        path: &mut Vec<usize>,
+        before: &mut usize,
+    ) -> bool {
+        if pred(self, path.len()) {
+            return true;
+        }
+        match self {
+            NestedList::Value(_) => *before += 1,
+            NestedList::List(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    path.push(i);
+                    if item.find_from(pred, path, before) {
+                        return true;
+                    }
+                    path.pop();
+                }
+            }
+        }
+        false
+    }
+
+    // Replace the first value (depth first) for which f returns a new node.
+    pub fn split(&mut self, f: impl Fn(&T) -> Option<NestedList<T>>) -> bool {
+        let Some((path, _)) = self.find(&|node, _| node.value().is_some_and(|v| f(v).is_some()))
+        else {
+            return false;
+        };
+        let node = self.get_mut(&path).unwrap();
+        if let Some(new) = node.value().and_then(&f) {
+            *node = new;
+        }
+        true
+    }
+}
+
+impl<T: Copy + Zero + Add<Output = T>> NestedList<T> {
+    // Find the first pair of values nested at least `depth` lists down, add
+    // its values to the neighbouring values on each side and replace it with
+    // zero.
+    pub fn explode(&mut self, depth: usize) -> bool {
+        let is_pair = |node: &NestedList<T>, d: usize| {
+            d >= depth
+                && node
+                    .items()
+                    .is_some_and(|items| items.len() == 2 && items.iter().all(|i| i.is_value()))
+        };
+        let Some((path, before)) = self.find(&is_pair) else {
+            return false;
+        };
+        let node = self.get_mut(&path).unwrap();
+        let (left, right) = match node.flatten()[..] {
+            [&l, &r] => (l, r),
+            _ => unreachable!(),
+        };
+        *node = NestedList::Value(T::zero());
+        let mut values = self.flatten_mut();
+        if before > 0 {
+            *values[before - 1] = *values[before - 1] + left;
+        }
+        if let Some(v) = values.get_mut(before + 1) {
+            **v = **v + right;
+        }
+        true
+    }
+}
+
+impl<T: Ord> Ord for NestedList<T> {
+    // Lists compare item by item, and a value compared against a list is
+    // treated as a list holding just that value.
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (NestedList::Value(a), NestedList::Value(b)) => a.cmp(b),
+            (NestedList::List(a), NestedList::List(b)) => a.iter().cmp(b.iter()),
+            (NestedList::Value(_), NestedList::List(b)) => {
+                std::slice::from_ref(self).iter().cmp(b.iter())
+            }
+            (NestedList::List(a), NestedList::Value(_)) => {
+                a.iter().cmp(std::slice::from_ref(other).iter())
+            }
+        }
+    }
+}
+
+impl<T: Ord> PartialOrd for NestedList<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Equality follows the ordering, so 1 == [1] == [[1]]
+impl<T: Ord> PartialEq for NestedList<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T: Ord> Eq for NestedList<T> {}
+
+impl<T: Display> Display for NestedList<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NestedList::Value(v) => write!(f, "{v}"),
+            NestedList::List(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+fn skip_whitespace(chars: &mut Peekable<impl Iterator<Item = char>>) {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+}
+
+// Whitespace is allowed around every element and separator.
+fn parse_node<T: FromStr>(
+    chars: &mut Peekable<impl Iterator<Item = char>>,
+) -> Option<NestedList<T>> {
+    skip_whitespace(chars);
+    if chars.next_if_eq(&'[').is_some() {
+        let mut items = Vec::new();
+        skip_whitespace(chars);
+        if chars.next_if_eq(&']').is_some() {
+            return Some(NestedList::List(items));
+        }
+        loop {
+            items.push(parse_node(chars)?);
+            skip_whitespace(chars);
+            match chars.next()? {
+                ',' => continue,
+                ']' => return Some(NestedList::List(items)),
+                _ => return None,
+            }
+        }
+    }
+    let mut token = String::new();
+    while let Some(c) = chars.next_if(|&c| c != ',' && c != ']') {
+        token.push(c);
+    }
+    Some(NestedList::Value(token.trim().parse().ok()?))
+}
+
+impl<T: FromStr> FromStr for NestedList<T> {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.trim().chars().peekable();
+        let list = parse_node(&mut chars).ok_or(())?;
+        if chars.next().is_some() {
+            return Err(());
+        }
+        Ok(list)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Packet = NestedList<u32>;
+
+    fn packet(s: &str) -> Packet {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let p = packet("[1,[2,[3,[4,[5,6,7]]]],8,9]");
+        assert_eq!(p.to_string(), "[1,[2,[3,[4,[5,6,7]]]],8,9]");
+        assert_eq!(p.depth(), 5);
+        assert_eq!(p.flatten(), vec![&1, &2, &3, &4, &5, &6, &7, &8, &9]);
+        assert_eq!(packet("[[],[[]]]").to_string(), "[[],[[]]]");
+        assert!("[1,2".parse::<Packet>().is_err());
+        assert!("[1,x]".parse::<Packet>().is_err());
+        assert!("[1]]".parse::<Packet>().is_err());
+        assert_eq!(
+            packet("[ 1, [2] , [ ], [[3 ] ]]").to_string(),
+            "[1,[2],[],[[3]]]"
+        );
+        assert!("[1 2]".parse::<Packet>().is_err());
+    }
+
+    #[test]
+    fn test_ordering() {
+        let pairs = "[1,1,3,1,1]
+[1,1,5,1,1]
+
+[[1],[2,3,4]]
+[[1],4]
+
+[9]
+[[8,7,6]]
+
+[[4,4],4,4]
+[[4,4],4,4,4]
+
+[7,7,7,7]
+[7,7,7]
+
+[]
+[3]
+
+[[[]]]
+[[]]
+
+[1,[2,[3,[4,[5,6,7]]]],8,9]
+[1,[2,[3,[4,[5,6,0]]]],8,9]";
+        let packets = pairs
+            .lines()
+            .filter(|l| !l.is_empty())
+            .map(packet)
+            .collect::<Vec<_>>();
+        let ordered = packets
+            .chunks(2)
+            .enumerate()
+            .filter(|(_, pair)| pair[0] < pair[1])
+            .map(|(i, _)| i + 1)
+            .sum::<usize>();
+        assert_eq!(ordered, 13);
+
+        let dividers = [packet("[[2]]"), packet("[[6]]")];
+        let mut all = packets.clone();
+        all.extend(dividers.iter().cloned());
+        all.sort();
+        let key = dividers
+            .iter()
+            .map(|d| {
+                all.iter()
+                    .position(|p| p.to_string() == d.to_string())
+                    .unwrap()
+                    + 1
+            })
+            .product::<usize>();
+        assert_eq!(key, 140);
+        assert_eq!(packet("[[1]]"), packet("1"));
+    }
+
+    #[test]
+    fn test_snailfish() {
+        let explode = |s: &str| {
+            let mut n = packet(s);
+            assert!(n.explode(4));
+            n.to_string()
+        };
+        assert_eq!(explode("[[[[[9,8],1],2],3],4]"), "[[[[0,9],2],3],4]");
+        assert_eq!(explode("[7,[6,[5,[4,[3,2]]]]]"), "[7,[6,[5,[7,0]]]]");
+        assert_eq!(
+            explode("[[3,[2,[1,[7,3]]]],[6,[5,[4,[3,2]]]]]"),
+            "[[3,[2,[8,0]]],[9,[5,[4,[3,2]]]]]"
+        );
+
+        let halve = |&v: &u32| {
+            (v >= 10).then(|| {
+                NestedList::List(vec![
+                    NestedList::Value(v / 2),
+                    NestedList::Value(v.div_ceil(2)),
+                ])
+            })
+        };
+        let mut n = packet("[[[[[4,3],4],4],[7,[[8,4],9]]],[1,1]]");
+        while n.explode(4) || n.split(halve) {}
+        assert_eq!(n.to_string(), "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]");
+        assert!(!n.split(halve));
+    }
+}
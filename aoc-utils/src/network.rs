@@ -0,0 +1,380 @@
+// A discrete event network: named nodes wired output to input, passing
+// messages which are delivered in the order they were sent. Each node is a
+// Component which may answer a message by sending one to all of its outputs.
+use std::collections::{HashMap, VecDeque};
+
+use crate::math::lcm_all;
+
+pub type NodeId = usize;
+
+pub trait Component<M> {
+    // Handle a message, returning the message to send to every output.
+    fn receive(&mut self, from: NodeId, message: &M) -> Option<M>;
+
+    // Called once for each input wired to this component.
+    fn connect(&mut self, _input: NodeId) {}
+}
+
+// Forwards every message.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Broadcast;
+
+impl<M: Clone> Component<M> for Broadcast {
+    fn receive(&mut self, _from: NodeId, message: &M) -> Option<M> {
+        Some(message.clone())
+    }
+}
+
+// Swallows every message.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Sink;
+
+impl<M> Component<M> for Sink {
+    fn receive(&mut self, _from: NodeId, _message: &M) -> Option<M> {
+        None
+    }
+}
+
+// Ignores high pulses, and toggles on a low pulse, sending its new state.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct FlipFlop(pub bool);
+
+impl Component<bool> for FlipFlop {
+    fn receive(&mut self, _from: NodeId, &high: &bool) -> Option<bool> {
+        if high {
+            return None;
+        }
+        self.0 = !self.0;
+        Some(self.0)
+    }
+}
+
+// Remembers the last pulse from each input, sending low once they're all
+// high and high otherwise.
+#[derive(Debug, Clone, Default)]
+pub struct Conjunction(pub HashMap<NodeId, bool>);
+
+impl Component<bool> for Conjunction {
+    fn receive(&mut self, from: NodeId, &high: &bool) -> Option<bool> {
+        self.0.insert(from, high);
+        Some(!self.0.values().all(|&h| h))
+    }
+
+    fn connect(&mut self, input: NodeId) {
+        self.0.insert(input, false);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Envelope<M> {
+    pub from: NodeId,
+    pub to: NodeId,
+    pub message: M,
+}
+
+pub struct Network<M> {
+    names: Vec<String>,
+    ids: HashMap<String, NodeId>,
+    components: Vec<Box<dyn Component<M>>>,
+    inputs: Vec<Vec<NodeId>>,
+    outputs: Vec<Vec<NodeId>>,
+    queue: VecDeque<Envelope<M>>,
+    rounds: usize,
+}
+
+impl<M: Clone> Default for Network<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M: Clone> Network<M> {
+    pub fn new() -> Network<M> {
+        Network {
+            names: Vec::new(),
+            ids: HashMap::new(),
+            components: Vec::new(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            queue: VecDeque::new(),
+            rounds: 0,
+        }
+    }
+
+    // Add a node, or replace the component of an existing one.
+    pub fn add(&mut self, name: &str, component: impl Component<M> + 'static) -> NodeId {
+        if let Some(&id) = self.ids.get(name) {
+            let mut component = Box::new(component);
+            for &input in &self.inputs[id] {
+                component.connect(input);
+            }
+            self.components[id] = component;
+            return id;
+        }
+        self.names.push(name.to_string());
+        self.ids.insert(name.to_string(), self.names.len() - 1);
+        self.components.push(Box::new(component));
+        self.inputs.push(Vec::new());
+        self.outputs.push(Vec::new());
+        self.names.len() - 1
+    }
+
+    // The id of a node, adding it as a Sink if it's new.
+    pub fn node(&mut self, name: &str) -> NodeId {
+        match self.ids.get(name) {
+            Some(&id) => id,
+            None => self.add(name, Sink),
+        }
+    }
+
+    pub fn id(&self, name: &str) -> Option<NodeId> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, id: NodeId) -> &str {
+        &self.names[id]
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn inputs(&self, id: NodeId) -> &[NodeId] {
+        &self.inputs[id]
+    }
+
+    pub fn outputs(&self, id: NodeId) -> &[NodeId] {
+        &self.outputs[id]
+    }
+
+    pub fn connect(&mut self, from: &str, to: &str) {
+        let (from, to) = (self.node(from), self.node(to));
+        self.outputs[from].push(to);
+        self.inputs[to].push(from);
+        self.components[to].connect(from);
+    }
+
+    // Times send has been called
+    pub fn rounds(&self) -> usize {
+        self.rounds
+    }
+
+    // Send a message and deliver everything that follows from it, showing
+    // each delivery to the observer. Returns the number of deliveries.
+    pub fn send(
+        &mut self,
+        from: NodeId,
+        to: NodeId,
+        message: M,
+        mut observe: impl FnMut(&Envelope<M>),
+    ) -> usize {
+        self.rounds += 1;
+        self.queue.push_back(Envelope { from, to, message });
+        let mut delivered = 0;
+        while let Some(envelope) = self.queue.pop_front() {
+            delivered += 1;
+            observe(&envelope);
+            let Envelope { from, to, message } = envelope;
+            if let Some(reply) = self.components[to].receive(from, &message) {
+                self.queue
+                    .extend(self.outputs[to].iter().map(|&next| Envelope {
+                        from: to,
+                        to: next,
+                        message: reply.clone(),
+                    }));
+            }
+        }
+        delivered
+    }
+
+    // Keep sending the trigger until each watched node has sent a message
+    // matching pred on every multiple of some number of rounds, seen at least
+    // `confirm` times. Returns those periods, or None if any is not found
+    // within max_rounds.
+    pub fn find_periods(
+        &mut self,
+        trigger: &Envelope<M>,
+        watch: &[NodeId],
+        pred: impl Fn(&M) -> bool,
+        confirm: usize,
+        max_rounds: usize,
+    ) -> Option<Vec<usize>> {
+        let mut hits = vec![Vec::new(); watch.len()];
+        let periodic = |hits: &[usize]| {
+            hits.len() >= confirm.max(1)
+                && hits
+                    .iter()
+                    .enumerate()
+                    .all(|(i, &h)| h == (i + 1) * hits[0])
+        };
+        for round in 1..=max_rounds {
+            let Envelope { from, to, message } = trigger.clone();
+            self.send(from, to, message, |e| {
+                if let Some(w) = watch.iter().position(|&w| w == e.from) {
+                    if pred(&e.message) && hits[w].last() != Some(&round) {
+                        hits[w].push(round);
+                    }
+                }
+            });
+            if hits.iter().all(|h| periodic(h)) {
+                return Some(hits.iter().map(|h| h[0]).collect());
+            }
+        }
+        None
+    }
+
+    // The first round on which all the watched nodes send a matching message,
+    // from the lowest common multiple of their periods.
+    pub fn first_coincidence(
+        &mut self,
+        trigger: &Envelope<M>,
+        watch: &[NodeId],
+        pred: impl Fn(&M) -> bool,
+        max_rounds: usize,
+    ) -> Option<usize> {
+        let periods = self.find_periods(trigger, watch, pred, 2, max_rounds)?;
+        Some(lcm_all(periods))
+    }
+}
+
+impl Network<bool> {
+    // Parse modules like "%a -> b, c" for a flip-flop, "&inv -> a" for a
+    // conjunction and "broadcaster -> a" for anything else, which forwards.
+    // A "button" node is added, wired to the broadcaster.
+    pub fn parse_pulses(input: &str) -> Option<Network<bool>> {
+        let mut network = Network::new();
+        let mut wires = Vec::new();
+        for line in input.lines().filter(|l| !l.trim().is_empty()) {
+            let (module, outputs) = line.trim().split_once(" -> ")?;
+            let name = if let Some(name) = module.strip_prefix('%') {
+                network.add(name, FlipFlop::default());
+                name
+            } else if let Some(name) = module.strip_prefix('&') {
+                network.add(name, Conjunction::default());
+                name
+            } else {
+                network.add(module, Broadcast);
+                module
+            };
+            wires.extend(outputs.split(", ").map(|to| (name, to.trim())));
+        }
+        network.add("button", Sink);
+        network.connect("button", "broadcaster");
+        for (from, to) in wires {
+            network.connect(from, to);
+        }
+        Some(network)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(network: &mut Network<bool>, times: usize) -> usize {
+        let button = network.id("button").unwrap();
+        let broadcaster = network.id("broadcaster").unwrap();
+        let (mut low, mut high) = (0, 0);
+        for _ in 0..times {
+            network.send(button, broadcaster, false, |e| match e.message {
+                true => high += 1,
+                false => low += 1,
+            });
+        }
+        low * high
+    }
+
+    #[test]
+    fn test_pulses() {
+        let mut network = Network::parse_pulses(
+            "broadcaster -> a, b, c
+%a -> b
+%b -> c
+%c -> inv
+&inv -> a",
+        )
+        .unwrap();
+        assert_eq!(network.len(), 6);
+        assert_eq!(press(&mut network, 1000), 32000000);
+
+        let mut network = Network::parse_pulses(
+            "broadcaster -> a
+%a -> inv, con
+&inv -> b
+%b -> con
+&con -> output",
+        )
+        .unwrap();
+        assert_eq!(press(&mut network, 1000), 11687500);
+        assert_eq!(network.rounds(), 1000);
+        let output = network.id("output").unwrap();
+        assert_eq!(network.inputs(output), &[network.id("con").unwrap()]);
+
+        // Names starting with a multi-byte character
+        let network = Network::parse_pulses("broadcaster -> é\nécho -> é\n%é -> écho").unwrap();
+        assert!(network.id("écho").is_some());
+        assert!(network.id("é").is_some());
+    }
+
+    // Sends high on every nth message, and low otherwise
+    struct Every(usize, usize);
+
+    impl Component<bool> for Every {
+        fn receive(&mut self, _from: NodeId, _message: &bool) -> Option<bool> {
+            self.1 += 1;
+            Some(self.1.is_multiple_of(self.0))
+        }
+    }
+
+    // Three counters with coprime periods feeding a conjunction
+    fn counters() -> Network<bool> {
+        let mut network = Network::new();
+        network.add("start", Broadcast);
+        for (name, n) in [("a", 3), ("b", 5), ("c", 7)] {
+            network.add(name, Every(n, 0));
+            network.connect("start", name);
+            network.connect(name, "all");
+        }
+        network.add("all", Conjunction::default());
+        network.connect("all", "rx");
+        network
+    }
+
+    #[test]
+    fn test_periods() {
+        let mut network = counters();
+        let start = network.id("start").unwrap();
+        let rx = network.id("rx").unwrap();
+        let watch = ["a", "b", "c"].map(|n| network.id(n).unwrap());
+        let trigger = Envelope {
+            from: start,
+            to: start,
+            message: false,
+        };
+
+        let periods = network.find_periods(&trigger, &watch, |&m| m, 3, 100);
+        assert_eq!(periods, Some(vec![3, 5, 7]));
+        assert_eq!(network.rounds(), 21);
+        assert_eq!(network.find_periods(&trigger, &[rx], |&m| m, 2, 10), None);
+
+        let first = counters().first_coincidence(&trigger, &watch, |&m| m, 100);
+        assert_eq!(first, Some(105));
+
+        // Check by brute force
+        let mut network = counters();
+        let all_at = (1..=200).find(|_| {
+            let mut sent = [false; 3];
+            network.send(start, start, false, |e| {
+                if let Some(w) = watch.iter().position(|&w| w == e.from) {
+                    sent[w] |= e.message;
+                }
+            });
+            sent == [true; 3]
+        });
+        assert_eq!(all_at, Some(105));
+    }
+}
@@ -0,0 +1,505 @@
+// Things falling until they hit something: shapes and grains in a 2D space,
+// and boxes settling onto each other in 3D.
+use std::{collections::HashSet, hash::Hash};
+
+use crate::{
+    automaton::{find_cycle_recording, Cycle},
+    geometry::aabb3d::{Aabb3d, Axis3d},
+    grud::{GridPos, GridVec},
+};
+
+// A set of cells that moves as one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shape2d {
+    cells: Vec<GridPos>,
+}
+
+impl Shape2d {
+    pub fn new(cells: Vec<GridPos>) -> Shape2d {
+        Shape2d { cells }
+    }
+
+    // The '#' cells of a picture, with the bottom row at y = 0 and y
+    // increasing upwards.
+    pub fn parse(picture: &str) -> Shape2d {
+        let rows = picture.lines().collect::<Vec<_>>();
+        let cells = rows
+            .iter()
+            .enumerate()
+            .flat_map(|(row, line)| {
+                let y = (rows.len() - 1 - row) as i64;
+                line.chars()
+                    .enumerate()
+                    .filter(|&(_, c)| c == '#')
+                    .map(move |(x, _)| GridPos::new(x as i64, y))
+            })
+            .collect();
+        Shape2d { cells }
+    }
+
+    pub fn cells(&self) -> &[GridPos] {
+        &self.cells
+    }
+
+    pub fn at(&self, offset: GridVec) -> impl Iterator<Item = GridPos> + '_ {
+        self.cells.iter().map(move |&c| c + offset)
+    }
+}
+
+// Occupied cells, with gravity pulling one row at a time along the y axis.
+// Optionally there are walls either side of a range of columns, and a floor
+// row which, along with everything beyond it, is solid.
+#[derive(Debug, Clone)]
+pub struct Space2d {
+    occupied: HashSet<GridPos>,
+    gravity: GridVec,
+    columns: Option<(i64, i64)>,
+    floor: Option<i64>,
+}
+
+impl Space2d {
+    // Gravity is +y for pictures (y down the screen) and -y for heights.
+    pub fn new(gravity: GridVec) -> Space2d {
+        assert!(gravity.x == 0 && gravity.y.abs() == 1);
+        Space2d {
+            occupied: HashSet::new(),
+            gravity,
+            columns: None,
+            floor: None,
+        }
+    }
+
+    pub fn with_columns(self, min: i64, max: i64) -> Space2d {
+        Space2d {
+            columns: Some((min, max)),
+            ..self
+        }
+    }
+
+    pub fn with_floor(self, floor: i64) -> Space2d {
+        Space2d {
+            floor: Some(floor),
+            ..self
+        }
+    }
+
+    pub fn occupied(&self) -> &HashSet<GridPos> {
+        &self.occupied
+    }
+
+    pub fn len(&self) -> usize {
+        self.occupied.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.occupied.is_empty()
+    }
+
+    pub fn occupy(&mut self, pos: GridPos) {
+        self.occupied.insert(pos);
+    }
+
+    pub fn is_free(&self, pos: &GridPos) -> bool {
+        let in_columns = self
+            .columns
+            .is_none_or(|(min, max)| (min..=max).contains(&pos.x));
+        let above_floor = self
+            .floor
+            .is_none_or(|floor| (pos.y - floor) * self.gravity.y < 0);
+        in_columns && above_floor && !self.occupied.contains(pos)
+    }
+
+    pub fn fits(&self, shape: &Shape2d, offset: GridVec) -> bool {
+        shape.at(offset).all(|c| self.is_free(&c))
+    }
+
+    pub fn place(&mut self, shape: &Shape2d, offset: GridVec) {
+        self.occupied.extend(shape.at(offset));
+    }
+
+    // Push a shape sideways if it can go, then let it fall a row. Returns the
+    // new offset, and whether the shape has come to rest.
+    pub fn push_and_fall(
+        &self,
+        shape: &Shape2d,
+        offset: GridVec,
+        push: GridVec,
+    ) -> (GridVec, bool) {
+        let offset = if self.fits(shape, offset + push) {
+            offset + push
+        } else {
+            offset
+        };
+        if self.fits(shape, offset + self.gravity) {
+            (offset + self.gravity, false)
+        } else {
+            (offset, true)
+        }
+    }
+
+    // Drop a shape from an offset, taking a push before every fall, and
+    // place it where it comes to rest. Returns where it rests, or None if any
+    // of it passes the limit row.
+    pub fn drop_shape(
+        &mut self,
+        shape: &Shape2d,
+        offset: GridVec,
+        limit: i64,
+        mut pushes: impl FnMut() -> GridVec,
+    ) -> Option<GridVec> {
+        let mut offset = offset;
+        loop {
+            let (next, rested) = self.push_and_fall(shape, offset, pushes());
+            if shape.at(next).any(|c| (c.y - limit) * self.gravity.y > 0) {
+                return None;
+            }
+            offset = next;
+            if rested {
+                self.place(shape, offset);
+                return Some(offset);
+            }
+        }
+    }
+
+    // Drop a grain from start, at each step taking the first of the moves that
+    // leads to a free cell, and place it where none do. Returns where it rests,
+    // or None if start is blocked or the grain passes the limit row.
+    pub fn drop_grain(&mut self, start: GridPos, moves: &[GridVec], limit: i64) -> Option<GridPos> {
+        if !self.is_free(&start) {
+            return None;
+        }
+        let mut pos = start;
+        while let Some(next) = moves
+            .iter()
+            .map(|&m| pos + m)
+            .find(|next| self.is_free(next))
+        {
+            if (next.y - limit) * self.gravity.y > 0 {
+                return None;
+            }
+            pos = next;
+        }
+        self.occupied.insert(pos);
+        Some(pos)
+    }
+
+    // The row of the occupied cell furthest against gravity.
+    pub fn top(&self) -> Option<i64> {
+        let ys = self.occupied.iter().map(|p| p.y);
+        if self.gravity.y < 0 {
+            ys.max()
+        } else {
+            ys.min()
+        }
+    }
+
+    // Rows from the floor up to and including the top, or 0 without a floor.
+    pub fn height(&self) -> i64 {
+        match (self.floor, self.top()) {
+            (Some(floor), Some(top)) => (floor - top) * self.gravity.y,
+            _ => 0,
+        }
+    }
+
+    // How far down from the top the first occupied cell in each column is,
+    // up to depth.
+    pub fn profile(&self, depth: i64) -> Vec<i64> {
+        let (Some((min, max)), Some(top)) = (self.columns, self.top()) else {
+            return Vec::new();
+        };
+        (min..=max)
+            .map(|x| {
+                (0..depth)
+                    .find(|d| {
+                        let y = top + d * self.gravity.y;
+                        self.occupied.contains(&GridPos::new(x, y))
+                    })
+                    .unwrap_or(depth)
+            })
+            .collect()
+    }
+}
+
+// Step a state holding a space, dropping things into it, until the shape of
+// the top depth rows repeats along with the key of whatever drops next.
+// Records the height after each step, to extrapolate with the cycle.
+pub fn find_stack_cycle<S, K: Hash + Eq>(
+    state: &mut S,
+    step: impl FnMut(&mut S),
+    space: impl Fn(&S) -> &Space2d,
+    key: impl Fn(&S) -> K,
+    depth: i64,
+    max_steps: usize,
+) -> Option<(Cycle, Vec<i64>)> {
+    find_cycle_recording(
+        state,
+        step,
+        |s| (key(s), space(s).profile(depth)),
+        |s| space(s).height(),
+        max_steps,
+    )
+}
+
+// Which boxes rest directly on which.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SupportGraph {
+    below: Vec<Vec<usize>>,
+    above: Vec<Vec<usize>>,
+}
+
+impl SupportGraph {
+    // From pairs of (upper, lower) shapes for n shapes.
+    pub fn new(n: usize, rests_on: impl IntoIterator<Item = (usize, usize)>) -> SupportGraph {
+        let mut below = vec![Vec::new(); n];
+        let mut above = vec![Vec::new(); n];
+        for (upper, lower) in rests_on {
+            if !below[upper].contains(&lower) {
+                below[upper].push(lower);
+                above[lower].push(upper);
+            }
+        }
+        SupportGraph { below, above }
+    }
+
+    pub fn len(&self) -> usize {
+        self.below.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.below.is_empty()
+    }
+
+    // The shapes a shape rests on; none means it's on the floor.
+    pub fn below(&self, i: usize) -> &[usize] {
+        &self.below[i]
+    }
+
+    pub fn above(&self, i: usize) -> &[usize] {
+        &self.above[i]
+    }
+
+    // Whether a shape can go without anything else falling.
+    pub fn is_removable(&self, i: usize) -> bool {
+        self.above[i].iter().all(|&a| self.below[a].len() > 1)
+    }
+
+    // How many other shapes would fall if this one were removed.
+    pub fn chain_reaction(&self, i: usize) -> usize {
+        let mut remaining = self.below.iter().map(|b| b.len()).collect::<Vec<_>>();
+        let mut falling = vec![i];
+        let mut fallen = 0;
+        while let Some(f) = falling.pop() {
+            for &a in &self.above[f] {
+                remaining[a] -= 1;
+                if remaining[a] == 0 {
+                    falling.push(a);
+                    fallen += 1;
+                }
+            }
+        }
+        fallen
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Settled {
+    pub boxes: Vec<Aabb3d<i64>>,
+    // The number of boxes that fell at all
+    pub moved: usize,
+    pub supports: SupportGraph,
+}
+
+// Let boxes fall down the z axis onto each other, no lower than the floor,
+// keeping them in their original order. Boxes are dropped lowest first, which
+// is only sound for boxes: a concave shape could be caught on something it
+// started below.
+pub fn settle(boxes: Vec<Aabb3d<i64>>, floor: i64) -> Settled {
+    let mut order = (0..boxes.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| boxes[i].min.z);
+    let mut boxes = boxes;
+    let mut placed: Vec<usize> = Vec::new();
+    let mut rests_on = Vec::new();
+    let mut moved = 0;
+    for i in order {
+        let dropped = boxes[i].drop_along(Axis3d::Z, floor, placed.iter().map(|&j| &boxes[j]));
+        if dropped != boxes[i] {
+            moved += 1;
+        }
+        boxes[i] = dropped;
+        rests_on.extend(
+            placed
+                .iter()
+                .filter(|&&j| boxes[j].max.z + 1 == dropped.min.z)
+                .filter(|&&j| dropped.shadows(&boxes[j], Axis3d::Z))
+                .map(|&j| (i, j)),
+        );
+        placed.push(i);
+    }
+    let supports = SupportGraph::new(boxes.len(), rests_on);
+    Settled {
+        boxes,
+        moved,
+        supports,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pos3d::Pos3d;
+
+    const ROCKS: [&str; 5] = [
+        "####",
+        ".#.\n###\n.#.",
+        "..#\n..#\n###",
+        "#\n#\n#\n#",
+        "##\n##",
+    ];
+
+    struct Tetris {
+        space: Space2d,
+        rocks: Vec<Shape2d>,
+        jets: Vec<GridVec>,
+        jet: usize,
+        dropped: usize,
+    }
+
+    impl Tetris {
+        fn new(jets: &str) -> Tetris {
+            let jets = jets
+                .trim()
+                .chars()
+                .map(|c| GridVec::new(if c == '<' { -1 } else { 1 }, 0))
+                .collect();
+            Tetris {
+                space: Space2d::new(GridVec::new(0, -1))
+                    .with_columns(0, 6)
+                    .with_floor(-1),
+                rocks: ROCKS.iter().map(|r| Shape2d::parse(r)).collect(),
+                jets,
+                jet: 0,
+                dropped: 0,
+            }
+        }
+
+        fn drop(&mut self) {
+            let rock = &self.rocks[self.dropped % self.rocks.len()];
+            let start = GridVec::new(2, self.space.height() + 3);
+            let (jets, jet) = (&self.jets, &mut self.jet);
+            self.space.drop_shape(rock, start, -1, || {
+                let push = jets[*jet];
+                *jet = (*jet + 1) % jets.len();
+                push
+            });
+            self.dropped += 1;
+        }
+    }
+
+    #[test]
+    fn test_rocks() {
+        const JETS: &str = ">>><<><>><<<>><>>><<<>>><<<><<<>><>><<>>";
+        let mut tetris = Tetris::new(JETS);
+        for _ in 0..2022 {
+            tetris.drop();
+        }
+        assert_eq!(tetris.space.height(), 3068);
+
+        // Nothing to land on, so the rock falls past the limit
+        let mut open = Space2d::new(GridVec::new(0, -1)).with_columns(0, 6);
+        let rock = Shape2d::parse(ROCKS[0]);
+        assert_eq!(
+            open.drop_shape(&rock, GridVec::new(2, 3), -100, || GridVec::new(1, 0)),
+            None
+        );
+        assert!(open.is_empty());
+
+        let mut tetris = Tetris::new(JETS);
+        let (cycle, heights) = find_stack_cycle(
+            &mut tetris,
+            Tetris::drop,
+            |t| &t.space,
+            |t| (t.dropped % 5, t.jet),
+            30,
+            5000,
+        )
+        .unwrap();
+        let height = cycle.extrapolate(&heights, 1_000_000_000_000);
+        assert_eq!(height, Some(1514285714288));
+    }
+
+    #[test]
+    fn test_sand() {
+        let paths = "498,4 -> 498,6 -> 496,6\n503,4 -> 502,4 -> 502,9 -> 494,9";
+        let mut space = Space2d::new(GridVec::new(0, 1));
+        for path in paths.lines() {
+            let points = path
+                .split(" -> ")
+                .map(|p| {
+                    let (x, y) = p.split_once(',').unwrap();
+                    GridPos::new(x.parse().unwrap(), y.parse().unwrap())
+                })
+                .collect::<Vec<_>>();
+            for w in points.windows(2) {
+                let (a, b) = (w[0], w[1]);
+                for x in a.x.min(b.x)..=a.x.max(b.x) {
+                    for y in a.y.min(b.y)..=a.y.max(b.y) {
+                        space.occupy(GridPos::new(x, y));
+                    }
+                }
+            }
+        }
+        let bottom = space.occupied().iter().map(|p| p.y).max().unwrap();
+        let moves = [(0, 1), (-1, 1), (1, 1)].map(|(x, y)| GridVec::new(x, y));
+        let source = GridPos::new(500, 0);
+
+        let mut abyss = space.clone();
+        let grains = std::iter::from_fn(|| abyss.drop_grain(source, &moves, bottom)).count();
+        assert_eq!(grains, 24);
+
+        let mut floored = space.with_floor(bottom + 2);
+        let grains = std::iter::from_fn(|| floored.drop_grain(source, &moves, i64::MAX)).count();
+        assert_eq!(grains, 93);
+    }
+
+    #[test]
+    fn test_bricks() {
+        let input = "1,0,1~1,2,1
+0,0,2~2,0,2
+0,2,3~2,2,3
+0,0,4~0,2,4
+2,0,5~2,2,5
+0,1,6~2,1,6
+1,1,8~1,1,9";
+        let bricks = input
+            .lines()
+            .map(|line| {
+                let ends = line
+                    .split('~')
+                    .map(|end| {
+                        let v = end
+                            .split(',')
+                            .map(|n| n.parse().unwrap())
+                            .collect::<Vec<_>>();
+                        Pos3d::new(v[0], v[1], v[2])
+                    })
+                    .collect::<Vec<_>>();
+                Aabb3d::from_corners(ends[0], ends[1])
+            })
+            .collect::<Vec<_>>();
+        let settled = settle(bricks, 1);
+        assert_eq!(settled.moved, 5);
+        assert_eq!(settled.boxes[6].min, Pos3d::new(1, 1, 5));
+        assert_eq!(settled.boxes[6].cells().count(), 2);
+        let supports = &settled.supports;
+        assert_eq!(supports.below(0), &[] as &[usize]);
+        assert_eq!(supports.above(0), &[1, 2]);
+        let removable = (0..supports.len())
+            .filter(|&i| supports.is_removable(i))
+            .count();
+        assert_eq!(removable, 5);
+        let falls = (0..supports.len())
+            .map(|i| supports.chain_reaction(i))
+            .sum::<usize>();
+        assert_eq!(falls, 7);
+    }
+}
@@ -29,7 +29,7 @@ impl<Scalar: Num + PartialOrd> PartialOrd for Pos3d<Scalar> {
                 Some(Ordering::Equal) => self.x.partial_cmp(&other.x),
                 ord => ord,
             },
-            ord => return ord,
+            ord => ord,
         }
     }
 }
@@ -0,0 +1,301 @@
+use std::{fmt::Display, marker::PhantomData, str::FromStr};
+
+use num_bigint::{BigInt, BigUint};
+use num_traits::{ToPrimitive, Zero};
+
+pub const DIGITS: &str = "0123456789abcdefghijklmnopqrstuvwxyz";
+
+fn digit_char(d: u32) -> char {
+    DIGITS.as_bytes()[d as usize] as char
+}
+
+// Parse in any base from 2 to 36, ignoring case.
+pub fn parse_radix(s: &str, base: u32) -> Option<u128> {
+    assert!((2..=36).contains(&base));
+    if s.is_empty() {
+        return None;
+    }
+    s.chars().try_fold(0u128, |v, c| {
+        v.checked_mul(base as u128)?
+            .checked_add(c.to_digit(base)? as u128)
+    })
+}
+
+pub fn format_radix(v: u128, base: u32) -> String {
+    assert!((2..=36).contains(&base));
+    let mut digits = Vec::new();
+    let mut v = v;
+    loop {
+        digits.push(digit_char((v % base as u128) as u32));
+        v /= base as u128;
+        if v == 0 {
+            break;
+        }
+    }
+    digits.iter().rev().collect()
+}
+
+pub fn parse_radix_big(s: &str, base: u32) -> Option<BigUint> {
+    BigUint::parse_bytes(s.as_bytes(), base)
+}
+
+pub fn format_radix_big(v: &BigUint, base: u32) -> String {
+    v.to_str_radix(base)
+}
+
+// Balanced bases use digits from -(base - 1) / 2 to (base - 1) / 2, written
+// with `symbols` in that order, so the base is the number of symbols.
+pub const SNAFU: &str = "=-012";
+pub const BALANCED_TERNARY: &str = "-0+";
+
+fn balanced_base(symbols: &str) -> i32 {
+    let base = symbols.chars().count() as i32;
+    assert!(base >= 3 && base % 2 == 1, "Balanced bases must be odd");
+    base
+}
+
+fn balanced_digits(s: &str, symbols: &str) -> Option<Vec<i32>> {
+    let half = balanced_base(symbols) / 2;
+    if s.is_empty() {
+        return None;
+    }
+    s.chars()
+        .map(|c| Some(symbols.chars().position(|sym| sym == c)? as i32 - half))
+        .collect()
+}
+
+fn balanced_symbol(d: i32, symbols: &str) -> char {
+    symbols
+        .chars()
+        .nth((d + balanced_base(symbols) / 2) as usize)
+        .unwrap()
+}
+
+pub fn parse_balanced(s: &str, symbols: &str) -> Option<i128> {
+    let base = balanced_base(symbols) as i128;
+    balanced_digits(s, symbols)?
+        .iter()
+        .try_fold(0i128, |v, &d| v.checked_mul(base)?.checked_add(d as i128))
+}
+
+pub fn format_balanced(v: i128, symbols: &str) -> String {
+    let base = balanced_base(symbols) as i128;
+    let half = base / 2;
+    let mut digits = Vec::new();
+    let mut v = v;
+    loop {
+        // Shift into 0..base, then back to -half..=half
+        let d = (v + half).rem_euclid(base) - half;
+        digits.push(balanced_symbol(d as i32, symbols));
+        v = (v - d) / base;
+        if v == 0 {
+            break;
+        }
+    }
+    digits.iter().rev().collect()
+}
+
+pub fn parse_balanced_big(s: &str, symbols: &str) -> Option<BigInt> {
+    let base = BigInt::from(balanced_base(symbols));
+    let digits = balanced_digits(s, symbols)?;
+    Some(
+        digits
+            .iter()
+            .fold(BigInt::zero(), |v, &d| v * &base + BigInt::from(d)),
+    )
+}
+
+pub fn format_balanced_big(v: &BigInt, symbols: &str) -> String {
+    let base = balanced_base(symbols);
+    let half = base / 2;
+    let big_base = BigInt::from(base);
+    let mut digits = Vec::new();
+    let mut v = v.clone();
+    loop {
+        let shifted = (&v + half) % &big_base;
+        let r = shifted.to_i32().unwrap();
+        let d = (r + base) % base - half;
+        digits.push(balanced_symbol(d, symbols));
+        v = (v - d) / &big_base;
+        if v.is_zero() {
+            break;
+        }
+    }
+    digits.iter().rev().collect()
+}
+
+pub fn gray_encode(v: u64) -> u64 {
+    v ^ (v >> 1)
+}
+
+pub fn gray_decode(g: u64) -> u64 {
+    let mut v = g;
+    let mut shift = 1;
+    while shift < 64 {
+        v ^= v >> shift;
+        shift <<= 1;
+    }
+    v
+}
+
+// Packed binary coded decimal, one decimal digit per nibble.
+pub fn to_bcd(v: u64) -> Option<u64> {
+    let mut bcd = 0u64;
+    let mut v = v;
+    let mut shift = 0;
+    loop {
+        if shift >= 64 {
+            return None;
+        }
+        bcd |= (v % 10) << shift;
+        v /= 10;
+        shift += 4;
+        if v == 0 {
+            return Some(bcd);
+        }
+    }
+}
+
+pub fn from_bcd(bcd: u64) -> Option<u64> {
+    (0..16).rev().try_fold(0u64, |v, nibble| {
+        let d = (bcd >> (nibble * 4)) & 0xf;
+        (d < 10).then_some(v * 10 + d)
+    })
+}
+
+// A number that parses from and displays in a fixed base.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Radix<const BASE: u32>(pub u128);
+
+impl<const BASE: u32> FromStr for Radix<BASE> {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_radix(s, BASE).map(Radix).ok_or(())
+    }
+}
+
+impl<const BASE: u32> Display for Radix<BASE> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", format_radix(self.0, BASE))
+    }
+}
+
+pub trait BalancedDigits {
+    const SYMBOLS: &'static str;
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Snafu;
+
+impl BalancedDigits for Snafu {
+    const SYMBOLS: &'static str = SNAFU;
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Ternary;
+
+impl BalancedDigits for Ternary {
+    const SYMBOLS: &'static str = BALANCED_TERNARY;
+}
+
+// A number that parses from and displays with a balanced digit set.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Balanced<D: BalancedDigits>(pub i128, PhantomData<D>);
+
+impl<D: BalancedDigits> Balanced<D> {
+    pub fn new(v: i128) -> Balanced<D> {
+        Balanced(v, PhantomData)
+    }
+}
+
+impl<D: BalancedDigits> FromStr for Balanced<D> {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_balanced(s, D::SYMBOLS).map(Balanced::new).ok_or(())
+    }
+}
+
+impl<D: BalancedDigits> Display for Balanced<D> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", format_balanced(self.0, D::SYMBOLS))
+    }
+}
+
+impl<D: BalancedDigits> std::iter::Sum for Balanced<D> {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        Balanced::new(iter.map(|b| b.0).sum())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_bigint::Sign;
+
+    #[test]
+    fn test_radix() {
+        assert_eq!(parse_radix("FF", 16), Some(255));
+        assert_eq!(parse_radix("102", 2), None);
+        assert_eq!(parse_radix("", 10), None);
+        assert_eq!(format_radix(255, 2), "11111111");
+        assert_eq!(format_radix(0, 7), "0");
+        assert_eq!("zz".parse::<Radix<36>>(), Ok(Radix(1295)));
+        assert_eq!(Radix::<16>(0xd2fe28).to_string(), "d2fe28");
+
+        let big = BigUint::from(1u8) << 200;
+        let s = format_radix_big(&big, 36);
+        assert_eq!(parse_radix_big(&s, 36), Some(big));
+    }
+
+    #[test]
+    fn test_snafu() {
+        let numbers = [
+            ("1=-0-2", 1747),
+            ("12111", 906),
+            ("2=0=", 198),
+            ("21", 11),
+            ("2=01", 201),
+            ("111", 31),
+            ("20012", 1257),
+            ("112", 32),
+            ("1=-1=", 353),
+            ("1-12", 107),
+            ("12", 7),
+            ("1=", 3),
+            ("122", 37),
+        ];
+        for (s, v) in numbers {
+            assert_eq!(parse_balanced(s, SNAFU), Some(v));
+            assert_eq!(format_balanced(v, SNAFU), s);
+        }
+        let total = numbers
+            .iter()
+            .map(|(s, _)| s.parse::<Balanced<Snafu>>().unwrap())
+            .sum::<Balanced<Snafu>>();
+        assert_eq!(total.to_string(), "2=-1=0");
+
+        assert_eq!(format_balanced(8, BALANCED_TERNARY), "+0-");
+        assert_eq!(format_balanced(-8, BALANCED_TERNARY), "-0+");
+        assert_eq!(Balanced::<Ternary>::new(0).to_string(), "0");
+
+        let big = BigInt::from(12345) - (BigInt::from(1u8) << 150usize);
+        let s = format_balanced_big(&big, SNAFU);
+        assert_eq!(parse_balanced_big(&s, SNAFU), Some(big.clone()));
+        assert_eq!(big.sign(), Sign::Minus);
+        assert!(s.starts_with('-') || s.starts_with('='));
+    }
+
+    #[test]
+    fn test_gray_bcd() {
+        let gray = (0..8).map(gray_encode).collect::<Vec<_>>();
+        assert_eq!(gray, vec![0, 1, 3, 2, 6, 7, 5, 4]);
+        assert!((0..1000).all(|v| gray_decode(gray_encode(v)) == v));
+        assert_eq!(gray_decode(gray_encode(u64::MAX)), u64::MAX);
+        assert_eq!(to_bcd(1234), Some(0x1234));
+        assert_eq!(from_bcd(0x1234), Some(1234));
+        assert_eq!(from_bcd(0x12a4), None);
+        assert_eq!(to_bcd(u64::MAX), None);
+    }
+}
@@ -0,0 +1,424 @@
+use std::{fmt::Display, ops::Range};
+
+use num_traits::{PrimInt, Signed};
+
+fn is_empty<T: PrimInt>(r: &Range<T>) -> bool {
+    r.start >= r.end
+}
+
+fn intersect<T: PrimInt>(a: &Range<T>, b: &Range<T>) -> Range<T> {
+    a.start.max(b.start)..a.end.min(b.end)
+}
+
+// A set of integers stored as sorted, disjoint, half open ranges.
+// Touching ranges are merged, so each range is separated by a gap.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct IntervalSet<T: PrimInt> {
+    ranges: Vec<Range<T>>,
+}
+
+impl<T: PrimInt + Display> Display for IntervalSet<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{{")?;
+        for (i, r) in self.ranges.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}..{}", r.start, r.end)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+impl<T: PrimInt> IntervalSet<T> {
+    pub fn new() -> IntervalSet<T> {
+        IntervalSet { ranges: Vec::new() }
+    }
+
+    pub fn from_range(range: Range<T>) -> IntervalSet<T> {
+        let mut set = IntervalSet::new();
+        set.insert(range);
+        set
+    }
+
+    pub fn ranges(&self) -> &[Range<T>] {
+        &self.ranges
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Range<T>> {
+        self.ranges.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    // The number of values covered by the set.
+    pub fn len(&self) -> T {
+        self.ranges
+            .iter()
+            .fold(T::zero(), |len, r| len + (r.end - r.start))
+    }
+
+    pub fn min(&self) -> Option<T> {
+        self.ranges.first().map(|r| r.start)
+    }
+
+    pub fn max(&self) -> Option<T> {
+        self.ranges.last().map(|r| r.end - T::one())
+    }
+
+    pub fn contains(&self, v: T) -> bool {
+        let i = self.ranges.partition_point(|r| r.end <= v);
+        self.ranges.get(i).is_some_and(|r| r.start <= v)
+    }
+
+    pub fn insert(&mut self, range: Range<T>) {
+        if is_empty(&range) {
+            return;
+        }
+        // Everything that overlaps or touches the new range is merged into it
+        let first = self.ranges.partition_point(|r| r.end < range.start);
+        let last = self.ranges.partition_point(|r| r.start <= range.end);
+        let merged = if first < last {
+            self.ranges[first].start.min(range.start)..self.ranges[last - 1].end.max(range.end)
+        } else {
+            range
+        };
+        self.ranges.splice(first..last, [merged]);
+    }
+
+    pub fn insert_inclusive(&mut self, lo: T, hi: T) {
+        self.insert(lo..hi + T::one());
+    }
+
+    pub fn remove(&mut self, range: Range<T>) {
+        if is_empty(&range) {
+            return;
+        }
+        let first = self.ranges.partition_point(|r| r.end <= range.start);
+        let last = self.ranges.partition_point(|r| r.start < range.end);
+        if first >= last {
+            return;
+        }
+        let mut keep = Vec::new();
+        let (lo, hi) = (&self.ranges[first], &self.ranges[last - 1]);
+        if lo.start < range.start {
+            keep.push(lo.start..range.start);
+        }
+        if hi.end > range.end {
+            keep.push(range.end..hi.end);
+        }
+        self.ranges.splice(first..last, keep);
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        let mut set = self.clone();
+        other.iter().for_each(|r| set.insert(r.clone()));
+        set
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.ranges.len() && j < other.ranges.len() {
+            let (a, b) = (&self.ranges[i], &other.ranges[j]);
+            let r = intersect(a, b);
+            if !is_empty(&r) {
+                ranges.push(r);
+            }
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        IntervalSet { ranges }
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut set = self.clone();
+        other.iter().for_each(|r| set.remove(r.clone()));
+        set
+    }
+
+    // The uncovered ranges within the bounds.
+    pub fn gaps(&self, bounds: Range<T>) -> Self {
+        IntervalSet::from_range(bounds).difference(self)
+    }
+}
+
+impl<T: PrimInt> FromIterator<Range<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Range<T>>>(iter: I) -> Self {
+        let mut set = IntervalSet::new();
+        iter.into_iter().for_each(|r| set.insert(r));
+        set
+    }
+}
+
+// Associates values with disjoint half open ranges.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct IntervalMap<T: PrimInt, V> {
+    entries: Vec<(Range<T>, V)>,
+}
+
+impl<T: PrimInt, V: Clone> IntervalMap<T, V> {
+    pub fn new() -> IntervalMap<T, V> {
+        IntervalMap {
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(Range<T>, V)> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // Insert the value over the range, replacing any values already there.
+    pub fn insert(&mut self, range: Range<T>, value: V) {
+        if is_empty(&range) {
+            return;
+        }
+        let first = self.entries.partition_point(|(r, _)| r.end <= range.start);
+        let last = self.entries.partition_point(|(r, _)| r.start < range.end);
+        let mut replacement = Vec::new();
+        if first < last {
+            let (lo, lo_v) = &self.entries[first];
+            if lo.start < range.start {
+                replacement.push((lo.start..range.start, lo_v.clone()));
+            }
+        }
+        replacement.push((range.clone(), value));
+        if first < last {
+            let (hi, hi_v) = &self.entries[last - 1];
+            if hi.end > range.end {
+                replacement.push((range.end..hi.end, hi_v.clone()));
+            }
+        }
+        self.entries.splice(first..last, replacement);
+    }
+
+    pub fn get(&self, v: T) -> Option<&V> {
+        let i = self.entries.partition_point(|(r, _)| r.end <= v);
+        self.entries
+            .get(i)
+            .filter(|(r, _)| r.start <= v)
+            .map(|(_, value)| value)
+    }
+
+    // Cut the range into pieces, each either covered by a single entry or
+    // not covered at all.
+    pub fn split(&self, range: Range<T>) -> Vec<(Range<T>, Option<&V>)> {
+        let mut pieces = Vec::new();
+        let mut pos = range.start;
+        let first = self.entries.partition_point(|(r, _)| r.end <= range.start);
+        for (r, value) in self.entries[first..].iter() {
+            if r.start >= range.end {
+                break;
+            }
+            if pos < r.start {
+                pieces.push((pos..r.start, None));
+            }
+            let piece = intersect(r, &range);
+            pos = piece.end;
+            pieces.push((piece, Some(value)));
+        }
+        if pos < range.end {
+            pieces.push((pos..range.end, None));
+        }
+        pieces
+    }
+}
+
+// A piecewise offset mapping: values in a range are shifted by its offset,
+// anything not covered maps to itself.
+pub type OffsetMap<T> = IntervalMap<T, T>;
+
+impl<T: PrimInt + Signed> IntervalMap<T, T> {
+    // Map src..src+len onto dst..dst+len
+    pub fn insert_mapping(&mut self, src: T, dst: T, len: T) {
+        self.insert(src..src + len, dst - src);
+    }
+
+    pub fn map_value(&self, v: T) -> T {
+        v + self.get(v).copied().unwrap_or(T::zero())
+    }
+
+    pub fn map_range(&self, range: Range<T>) -> Vec<Range<T>> {
+        self.split(range)
+            .into_iter()
+            .map(|(r, offset)| {
+                let offset = offset.copied().unwrap_or(T::zero());
+                r.start + offset..r.end + offset
+            })
+            .collect()
+    }
+
+    pub fn map_set(&self, set: &IntervalSet<T>) -> IntervalSet<T> {
+        set.iter().flat_map(|r| self.map_range(r.clone())).collect()
+    }
+}
+
+// An N dimensional box of half open ranges, e.g. the 4D xmas part ranges.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HyperRect<T: PrimInt, const N: usize> {
+    pub ranges: [Range<T>; N],
+}
+
+impl<T: PrimInt, const N: usize> HyperRect<T, N> {
+    pub fn new(ranges: [Range<T>; N]) -> HyperRect<T, N> {
+        HyperRect { ranges }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.iter().any(is_empty)
+    }
+
+    pub fn volume(&self) -> T {
+        if self.is_empty() {
+            T::zero()
+        } else {
+            self.ranges
+                .iter()
+                .fold(T::one(), |v, r| v * (r.end - r.start))
+        }
+    }
+
+    pub fn contains(&self, p: &[T; N]) -> bool {
+        self.ranges.iter().zip(p).all(|(r, v)| r.contains(v))
+    }
+
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        let mut ranges = self.ranges.clone();
+        ranges
+            .iter_mut()
+            .zip(other.ranges.iter())
+            .for_each(|(a, b)| *a = intersect(a, b));
+        let rect = HyperRect { ranges };
+        if rect.is_empty() {
+            None
+        } else {
+            Some(rect)
+        }
+    }
+
+    // Split along an axis into the parts below `at` and from `at` upwards.
+    pub fn split(&self, axis: usize, at: T) -> (Option<Self>, Option<Self>) {
+        let r = &self.ranges[axis];
+        let mut below = self.clone();
+        below.ranges[axis] = r.start..at.min(r.end);
+        let mut above = self.clone();
+        above.ranges[axis] = at.max(r.start)..r.end;
+        (
+            Some(below).filter(|b| !b.is_empty()),
+            Some(above).filter(|a| !a.is_empty()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interval_set() {
+        let mut set = IntervalSet::<i64>::from_iter([0..5, 10..15, 5..7]);
+        assert_eq!(set.ranges(), &[0..7, 10..15]);
+        assert_eq!(set.len(), 12);
+        assert!(set.contains(6));
+        assert!(!set.contains(7));
+        assert!(set.contains(10));
+
+        set.insert(6..11);
+        assert_eq!(set, IntervalSet::from_range(0..15));
+        set.remove(3..8);
+        assert_eq!(set.ranges(), &[0..3, 8..15]);
+        set.insert_inclusive(20, 20);
+        assert_eq!(set.max(), Some(20));
+        assert_eq!(set.gaps(-2..22).ranges(), &[-2..0, 3..8, 15..20, 21..22]);
+        assert_eq!(set.to_string(), "{0..3, 8..15, 20..21}");
+    }
+
+    #[test]
+    fn test_set_ops() {
+        let a = IntervalSet::<u32>::from_iter([0..10, 20..30]);
+        let b = IntervalSet::<u32>::from_range(5..25);
+        assert_eq!(a.union(&b), IntervalSet::from_range(0..30));
+        assert_eq!(a.intersection(&b).ranges(), &[5..10, 20..25]);
+        assert_eq!(a.difference(&b).ranges(), &[0..5, 25..30]);
+        assert_eq!(b.difference(&a), IntervalSet::from_range(10..20));
+    }
+
+    #[test]
+    fn test_interval_map() {
+        let mut map = IntervalMap::<i64, char>::new();
+        map.insert(0..10, 'a');
+        map.insert(5..15, 'b');
+        map.insert(7..8, 'c');
+        assert_eq!(map.get(4), Some(&'a'));
+        assert_eq!(map.get(7), Some(&'c'));
+        assert_eq!(map.get(8), Some(&'b'));
+        assert_eq!(map.get(15), None);
+        assert_eq!(
+            map.split(3..20),
+            vec![
+                (3..5, Some(&'a')),
+                (5..7, Some(&'b')),
+                (7..8, Some(&'c')),
+                (8..15, Some(&'b')),
+                (15..20, None)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_offset_maps() {
+        let almanac = [
+            vec![(50, 98, 2), (52, 50, 48)],
+            vec![(0, 15, 37), (37, 52, 2), (39, 0, 15)],
+            vec![(49, 53, 8), (0, 11, 42), (42, 0, 7), (57, 7, 4)],
+            vec![(88, 18, 7), (18, 25, 70)],
+            vec![(45, 77, 23), (81, 45, 19), (68, 64, 13)],
+            vec![(0, 69, 1), (1, 0, 69)],
+            vec![(60, 56, 37), (56, 93, 4)],
+        ];
+        let maps = almanac
+            .iter()
+            .map(|m| {
+                let mut map = OffsetMap::<i64>::new();
+                m.iter()
+                    .for_each(|&(dst, src, len)| map.insert_mapping(src, dst, len));
+                map
+            })
+            .collect::<Vec<_>>();
+
+        let seeds = [79, 14, 55, 13];
+        let locations = seeds.map(|s| maps.iter().fold(s, |v, m| m.map_value(v)));
+        assert_eq!(locations, [82, 43, 86, 35]);
+
+        let seeds = IntervalSet::from_iter([79..79 + 14, 55..55 + 13]);
+        let locations = maps.iter().fold(seeds, |set, m| m.map_set(&set));
+        assert_eq!(locations.min(), Some(46));
+    }
+
+    #[test]
+    fn test_hyper_rect() {
+        let parts = HyperRect::<u64, 4>::new([1..4001, 1..4001, 1..4001, 1..4001]);
+        assert_eq!(parts.volume(), 4000u64.pow(4));
+        let (lo, hi) = parts.split(1, 1351);
+        let (lo, hi) = (lo.unwrap(), hi.unwrap());
+        assert_eq!(lo.ranges[1], 1..1351);
+        assert_eq!(hi.ranges[1], 1351..4001);
+        assert_eq!(lo.volume() + hi.volume(), parts.volume());
+        assert_eq!(lo.split(1, 0), (None, Some(lo.clone())));
+        assert!(lo.contains(&[1, 1350, 4000, 1]));
+        assert_eq!(lo.intersection(&hi), None);
+    }
+}
@@ -0,0 +1,415 @@
+// Branch-and-bound search for the best state reachable from a start. Every
+// state has a value, the score if the search stopped there, and an upper
+// bound on the value of anything reachable from it; states whose bound can't
+// beat the best value so far are not expanded. States are expanded a layer
+// at a time, optionally across threads.
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    hash::Hash,
+    thread,
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct SearchStats {
+    pub explored: usize,
+    // States dropped because their bound couldn't beat the best
+    pub bounded: usize,
+    pub duplicates: usize,
+    pub dominated: usize,
+    // States dropped to keep layers within the beam width
+    pub beam: usize,
+}
+
+impl SearchStats {
+    pub fn pruned(&self) -> usize {
+        self.bounded + self.duplicates + self.dominated + self.beam
+    }
+}
+
+impl Display for SearchStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} explored, {} pruned ({} bounded, {} duplicates, {} dominated, {} beam)",
+            self.explored,
+            self.pruned(),
+            self.bounded,
+            self.duplicates,
+            self.dominated,
+            self.beam
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Best<S, V> {
+    pub state: S,
+    pub value: V,
+    pub stats: SearchStats,
+}
+
+type StateFn<'a, S, T> = Box<dyn Fn(&S) -> T + Sync + 'a>;
+type PairFn<'a, S> = Box<dyn Fn(&S, &S) -> bool + Sync + 'a>;
+
+struct Dominance<'a, S, K> {
+    key: StateFn<'a, S, K>,
+    dominates: PairFn<'a, S>,
+}
+
+pub struct Maximise<'a, S, V, K = ()> {
+    successors: StateFn<'a, S, Vec<S>>,
+    value: StateFn<'a, S, V>,
+    upper_bound: StateFn<'a, S, V>,
+    dedup: bool,
+    dominance: Option<Dominance<'a, S, K>>,
+    beam_width: Option<usize>,
+    threads: usize,
+}
+
+impl<'a, S, V> Maximise<'a, S, V>
+where
+    S: Clone + Hash + Eq + Send + Sync,
+    V: Ord + Copy + Send,
+{
+    pub fn new(
+        successors: impl Fn(&S) -> Vec<S> + Sync + 'a,
+        value: impl Fn(&S) -> V + Sync + 'a,
+        upper_bound: impl Fn(&S) -> V + Sync + 'a,
+    ) -> Maximise<'a, S, V> {
+        Maximise {
+            successors: Box::new(successors),
+            value: Box::new(value),
+            upper_bound: Box::new(upper_bound),
+            dedup: false,
+            dominance: None,
+            beam_width: None,
+            threads: 1,
+        }
+    }
+
+    // Drop states dominated by another state with the same key that has
+    // already been seen, where dominates(a, b) means b can do no better
+    // than a.
+    pub fn with_dominance<K: Hash + Eq>(
+        self,
+        key: impl Fn(&S) -> K + Sync + 'a,
+        dominates: impl Fn(&S, &S) -> bool + Sync + 'a,
+    ) -> Maximise<'a, S, V, K> {
+        Maximise {
+            successors: self.successors,
+            value: self.value,
+            upper_bound: self.upper_bound,
+            dedup: self.dedup,
+            dominance: Some(Dominance {
+                key: Box::new(key),
+                dominates: Box::new(dominates),
+            }),
+            beam_width: self.beam_width,
+            threads: self.threads,
+        }
+    }
+}
+
+impl<S, V, K> Maximise<'_, S, V, K>
+where
+    S: Clone + Hash + Eq + Send + Sync,
+    V: Ord + Copy + Send,
+    K: Hash + Eq,
+{
+    // Skip states that have been seen before.
+    pub fn with_dedup(self) -> Self {
+        Maximise {
+            dedup: true,
+            ..self
+        }
+    }
+
+    // Keep only the states with the highest bounds in each layer. The search
+    // is no longer exhaustive, so may miss the best state.
+    pub fn with_beam_width(self, width: usize) -> Self {
+        Maximise {
+            beam_width: Some(width),
+            ..self
+        }
+    }
+
+    pub fn with_threads(self, threads: usize) -> Self {
+        Maximise {
+            threads: threads.max(1),
+            ..self
+        }
+    }
+
+    // The successors of a layer of states, with their values and bounds
+    fn expand(&self, layer: &[S]) -> Vec<(S, V, V)> {
+        let expand = |state: &S| {
+            (self.successors)(state)
+                .into_iter()
+                .map(|s| {
+                    let (value, bound) = ((self.value)(&s), (self.upper_bound)(&s));
+                    (s, value, bound)
+                })
+                .collect::<Vec<_>>()
+        };
+        if self.threads == 1 || layer.len() < 2 * self.threads {
+            return layer.iter().flat_map(expand).collect();
+        }
+        let chunk = layer.len().div_ceil(self.threads);
+        thread::scope(|scope| {
+            let handles = layer
+                .chunks(chunk)
+                .map(|states| {
+                    scope.spawn(move || states.iter().flat_map(expand).collect::<Vec<_>>())
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().unwrap())
+                .collect()
+        })
+    }
+
+    pub fn run(&self, start: S) -> Best<S, V> {
+        let mut stats = SearchStats::default();
+        let mut best = (start.clone(), (self.value)(&start));
+        let mut seen = HashSet::new();
+        let mut frontiers: HashMap<K, Vec<S>> = HashMap::new();
+        let mut layer = vec![start];
+        while !layer.is_empty() {
+            stats.explored += layer.len();
+            let mut next = Vec::new();
+            for (state, value, bound) in self.expand(&layer) {
+                if value > best.1 {
+                    best = (state.clone(), value);
+                }
+                if bound <= best.1 {
+                    stats.bounded += 1;
+                    continue;
+                }
+                if self.dedup && !seen.insert(state.clone()) {
+                    stats.duplicates += 1;
+                    continue;
+                }
+                if let Some(dominance) = &self.dominance {
+                    let frontier = frontiers.entry((dominance.key)(&state)).or_default();
+                    if frontier.iter().any(|f| (dominance.dominates)(f, &state)) {
+                        stats.dominated += 1;
+                        continue;
+                    }
+                    frontier.retain(|f| !(dominance.dominates)(&state, f));
+                    frontier.push(state.clone());
+                }
+                next.push((state, bound));
+            }
+            // The best may have improved since earlier states were kept
+            let before = next.len();
+            next.retain(|&(_, bound)| bound > best.1);
+            stats.bounded += before - next.len();
+            if let Some(width) = self.beam_width {
+                if next.len() > width {
+                    next.sort_by_key(|&(_, bound)| Reverse(bound));
+                    stats.beam += next.len() - width;
+                    next.truncate(width);
+                }
+            }
+            layer = next.into_iter().map(|(state, _)| state).collect();
+        }
+        Best {
+            state: best.0,
+            value: best.1,
+            stats,
+        }
+    }
+}
+
+// Exhaustive branch-and-bound with no other pruning.
+pub fn maximise<S, V>(
+    start: S,
+    successors: impl Fn(&S) -> Vec<S> + Sync,
+    value: impl Fn(&S) -> V + Sync,
+    upper_bound: impl Fn(&S) -> V + Sync,
+) -> Best<S, V>
+where
+    S: Clone + Hash + Eq + Send + Sync,
+    V: Ord + Copy + Send,
+{
+    Maximise::new(successors, value, upper_bound).run(start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+    struct Mining {
+        time: u32,
+        robots: [u32; 4],
+        stock: [u32; 4],
+    }
+
+    impl Mining {
+        // The geodes there'll be if no more robots are built
+        fn geodes(&self) -> u32 {
+            self.stock[3] + self.robots[3] * self.time
+        }
+
+        // As if a geode robot could be built every minute from now
+        fn bound(&self) -> u32 {
+            self.geodes() + self.time * self.time.saturating_sub(1) / 2
+        }
+
+        // Wait until the next robot of each kind can be built, and build it
+        fn next(&self, costs: &[[u32; 4]; 4]) -> Vec<Mining> {
+            let most = (0..4)
+                .map(|i| costs.iter().map(|c| c[i]).max().unwrap())
+                .collect::<Vec<_>>();
+            (0..4)
+                .filter(|&r| r == 3 || self.robots[r] < most[r])
+                .filter_map(|r| {
+                    let waits = (0..4)
+                        .map(|i| match costs[r][i].saturating_sub(self.stock[i]) {
+                            0 => Some(0),
+                            _ if self.robots[i] == 0 => None,
+                            needed => Some(needed.div_ceil(self.robots[i])),
+                        })
+                        .collect::<Option<Vec<_>>>()?;
+                    let t = waits.iter().max().unwrap() + 1;
+                    if t >= self.time {
+                        return None;
+                    }
+                    let mut next = *self;
+                    next.time -= t;
+                    next.stock =
+                        std::array::from_fn(|i| self.stock[i] + self.robots[i] * t - costs[r][i]);
+                    next.robots[r] += 1;
+                    Some(next)
+                })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_blueprints() {
+        let blueprints = [
+            [[4, 0, 0, 0], [2, 0, 0, 0], [3, 14, 0, 0], [2, 0, 7, 0]],
+            [[2, 0, 0, 0], [3, 0, 0, 0], [3, 8, 0, 0], [3, 0, 12, 0]],
+        ];
+        let start = Mining {
+            time: 24,
+            robots: [1, 0, 0, 0],
+            stock: [0; 4],
+        };
+        let costs = &blueprints[0];
+        let best = maximise(start, |s| s.next(costs), Mining::geodes, Mining::bound);
+        assert_eq!(best.value, 9);
+        assert_eq!(best.state.geodes(), 9);
+        assert!(best.stats.bounded > 0);
+
+        let costs = &blueprints[1];
+        let search = Maximise::new(|s: &Mining| s.next(costs), Mining::geodes, Mining::bound)
+            .with_dedup()
+            .with_threads(4);
+        let best = search.run(start);
+        assert_eq!(best.value, 12);
+        assert!(best.stats.duplicates > 0);
+
+        let search = search.with_beam_width(1);
+        let narrow = search.run(start);
+        assert!(narrow.value <= 12);
+        assert!(narrow.stats.beam > 0);
+        assert!(narrow.stats.explored < best.stats.explored);
+    }
+
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+    struct Valves {
+        time: [u32; 2],
+        at: [usize; 2],
+        open: u64,
+        released: u32,
+    }
+
+    #[test]
+    fn test_valves() {
+        let input = "Valve AA has flow rate=0; tunnels lead to valves DD, II, BB
+Valve BB has flow rate=13; tunnels lead to valves CC, AA
+Valve CC has flow rate=2; tunnels lead to valves DD, BB
+Valve DD has flow rate=20; tunnels lead to valves CC, AA, EE
+Valve EE has flow rate=3; tunnels lead to valves FF, DD
+Valve FF has flow rate=0; tunnels lead to valves EE, GG
+Valve GG has flow rate=0; tunnels lead to valves FF, HH
+Valve HH has flow rate=22; tunnel leads to valve GG
+Valve II has flow rate=0; tunnels lead to valves AA, JJ
+Valve JJ has flow rate=21; tunnel leads to valve II";
+        let valves = input
+            .lines()
+            .map(|line| {
+                let (valve, tunnels) = line.split_once("; ").unwrap();
+                let words = valve.split(' ').collect::<Vec<_>>();
+                let rate = words[4].trim_start_matches("rate=").parse().unwrap();
+                let tunnels = tunnels.split(' ').skip(4).map(|t| t.trim_matches(','));
+                (words[1], rate, tunnels.collect::<Vec<_>>())
+            })
+            .collect::<Vec<(&str, u32, _)>>();
+        let n = valves.len();
+        let index = |name| valves.iter().position(|v| v.0 == name).unwrap();
+        let mut dist = vec![vec![u32::MAX / 2; n]; n];
+        for (i, (_, _, tunnels)) in valves.iter().enumerate() {
+            dist[i][i] = 0;
+            for &t in tunnels {
+                dist[i][index(t)] = 1;
+            }
+        }
+        for k in 0..n {
+            for i in 0..n {
+                for j in 0..n {
+                    dist[i][j] = dist[i][j].min(dist[i][k] + dist[k][j]);
+                }
+            }
+        }
+
+        // The valve-opener with the most time left either opens another
+        // valve or stops for good.
+        let successors = |s: &Valves| {
+            let who = if s.time[0] >= s.time[1] { 0 } else { 1 };
+            let mut next = (0..n)
+                .filter(|&v| valves[v].1 > 0 && s.open & (1 << v) == 0)
+                .filter_map(|v| {
+                    let time = s.time[who].checked_sub(dist[s.at[who]][v] + 1)?;
+                    let mut next = *s;
+                    next.time[who] = time;
+                    next.at[who] = v;
+                    next.open |= 1 << v;
+                    next.released += valves[v].1 * time;
+                    Some(next)
+                })
+                .collect::<Vec<_>>();
+            if s.time[1 - who] > 0 {
+                let mut stop = *s;
+                stop.time[who] = 0;
+                next.push(stop);
+            }
+            next
+        };
+        let bound = |s: &Valves| {
+            let time = s.time[0].max(s.time[1]).saturating_sub(1);
+            let closed = (0..n).filter(|&v| s.open & (1 << v) == 0);
+            s.released + closed.map(|v| valves[v].1 * time).sum::<u32>()
+        };
+        let search = Maximise::new(successors, |s: &Valves| s.released, bound).with_dominance(
+            |s| (s.at, s.open),
+            |a, b| a.released >= b.released && a.time[0] >= b.time[0] && a.time[1] >= b.time[1],
+        );
+        let start = |time| Valves {
+            time,
+            at: [index("AA"); 2],
+            open: 0,
+            released: 0,
+        };
+        assert_eq!(search.run(start([30, 0])).value, 1651);
+        let best = search.run(start([26, 26]));
+        assert_eq!(best.value, 1707);
+        assert!(best.stats.dominated > 0);
+    }
+}
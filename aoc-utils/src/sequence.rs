@@ -0,0 +1,187 @@
+use num_traits::{PrimInt, Signed};
+
+use crate::linalg::rational::Rational;
+
+// The rows of successive differences, starting with the values themselves and
+// ending with the first row that is all zeros (or empty if none is).
+pub fn difference_table<T: PrimInt + Signed>(values: &[T]) -> Vec<Vec<T>> {
+    let mut table = vec![values.to_vec()];
+    while let Some(row) = table.last().filter(|row| row.iter().any(|v| !v.is_zero())) {
+        let next = row.windows(2).map(|w| w[1] - w[0]).collect();
+        table.push(next);
+    }
+    table
+}
+
+// Extrapolate one step after the end.
+pub fn next_value<T: PrimInt + Signed>(values: &[T]) -> T {
+    difference_table(values)
+        .iter()
+        .filter_map(|row| row.last())
+        .fold(T::zero(), |acc, &v| acc + v)
+}
+
+// Extrapolate one step before the start.
+pub fn prev_value<T: PrimInt + Signed>(values: &[T]) -> T {
+    difference_table(values)
+        .iter()
+        .rev()
+        .filter_map(|row| row.first())
+        .fold(T::zero(), |acc, &v| v - acc)
+}
+
+// The degree of the polynomial through the values, if the differences reach
+// zero with at least one point to spare.
+pub fn polynomial_degree<T: PrimInt + Signed>(values: &[T]) -> Option<usize> {
+    let table = difference_table(values);
+    let zeros = table.last()?;
+    if zeros.is_empty() || zeros.iter().any(|v| !v.is_zero()) {
+        None
+    } else {
+        Some(table.len().saturating_sub(2))
+    }
+}
+
+// Evaluate the polynomial through the points at x, exactly.
+pub fn lagrange<T: PrimInt>(points: &[(T, T)], x: T) -> Rational<i128> {
+    let wide = |v: T| v.to_i128().expect("Value too large for i128");
+    let x = wide(x);
+    points
+        .iter()
+        .enumerate()
+        .fold(Rational::from(0), |sum, (i, &(xi, yi))| {
+            let term = points
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| j != i)
+                .fold(Rational::from(wide(yi)), |term, (_, &(xj, _))| {
+                    term * Rational::new(x - wide(xj), wide(xi) - wide(xj))
+                });
+            sum + term
+        })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pattern<T> {
+    // A polynomial in the index
+    Polynomial {
+        degree: usize,
+    },
+    // From `start`, every value is the one `period` before plus `delta`
+    Cycle {
+        start: usize,
+        period: usize,
+        delta: T,
+    },
+}
+
+impl<T: PrimInt + Signed> Pattern<T> {
+    // Look for a polynomial, then the shortest cycle that has held for at
+    // least `repeats` periods by the end of the values.
+    pub fn detect(values: &[T], repeats: usize) -> Option<Pattern<T>> {
+        if let Some(degree) = polynomial_degree(values) {
+            return Some(Pattern::Polynomial { degree });
+        }
+        (1..=values.len() / 2).find_map(|period| {
+            let deltas = (period..values.len())
+                .map(|i| values[i] - values[i - period])
+                .collect::<Vec<_>>();
+            let last = *deltas.last()?;
+            let tail = deltas.iter().rev().take_while(|&&d| d == last).count();
+            (tail >= repeats * period).then(|| Pattern::Cycle {
+                start: values.len() - period - tail,
+                period,
+                delta: last,
+            })
+        })
+    }
+
+    // The value at index n, given the sampled values the pattern was found in.
+    pub fn at(&self, values: &[T], n: u64) -> Option<T> {
+        if let Some(&v) = values.get(n as usize) {
+            return Some(v);
+        }
+        match *self {
+            Pattern::Polynomial { degree } => {
+                let from = values.len().checked_sub(degree + 1)?;
+                let points = (from..values.len())
+                    .map(|i| (i as i128, values[i].to_i128().unwrap()))
+                    .collect::<Vec<_>>();
+                T::from(lagrange(&points, n as i128).to_integer()?)
+            }
+            Pattern::Cycle {
+                start,
+                period,
+                delta,
+            } => {
+                let offset = n - start as u64;
+                let (k, r) = (offset / period as u64, offset % period as u64);
+                Some(values[start + r as usize] + delta * T::from(k)?)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extrapolate() {
+        let histories: [[i64; 6]; 3] = [
+            [0, 3, 6, 9, 12, 15],
+            [1, 3, 6, 10, 15, 21],
+            [10, 13, 16, 21, 30, 45],
+        ];
+        let next = histories.iter().map(|h| next_value(h)).collect::<Vec<_>>();
+        assert_eq!(next, vec![18, 28, 68]);
+        let prev = histories.iter().map(|h| prev_value(h)).collect::<Vec<_>>();
+        assert_eq!(prev, vec![-3, 0, 5]);
+        assert_eq!(polynomial_degree(&histories[2]), Some(3));
+        assert_eq!(polynomial_degree(&[1i64, 5, 2]), None);
+    }
+
+    #[test]
+    fn test_lagrange() {
+        // A quadratic sampled where the walk reaches the edge of each tile
+        let f = |s: i128| 3 * s * s + 5 * s - 7;
+        let points = [65, 196, 327].map(|s| (s, f(s)));
+        let steps = 26501365;
+        assert_eq!(lagrange(&points, steps).to_integer(), Some(f(steps)));
+        assert_eq!(lagrange(&[(0, 0), (2, 1)], 1), Rational::new(1, 2));
+    }
+
+    #[test]
+    fn test_pattern() {
+        let squares = (0..6i64).map(|i| i * i).collect::<Vec<_>>();
+        let pattern = Pattern::detect(&squares, 2).unwrap();
+        assert_eq!(pattern, Pattern::Polynomial { degree: 2 });
+        assert_eq!(pattern.at(&squares, 1000), Some(1000000));
+
+        // A run in followed by a growing cycle, like a tower of falling rocks
+        let heights = [1i64, 4, 6, 7, 9, 10, 13, 14, 16, 17, 20, 21, 23, 24];
+        let pattern = Pattern::detect(&heights, 2).unwrap();
+        assert_eq!(
+            pattern,
+            Pattern::Cycle {
+                start: 2,
+                period: 4,
+                delta: 7
+            }
+        );
+        assert_eq!(pattern.at(&heights, 14), Some(27));
+        assert_eq!(pattern.at(&heights, 17), Some(31));
+
+        let periodic = [5i64, 1, 2, 3, 1, 2, 3, 1, 2, 3, 1, 2, 3];
+        let pattern = Pattern::detect(&periodic, 3).unwrap();
+        assert_eq!(
+            pattern,
+            Pattern::Cycle {
+                start: 1,
+                period: 3,
+                delta: 0
+            }
+        );
+        assert_eq!(pattern.at(&periodic, 1_000_000_000_000), Some(1));
+    }
+}
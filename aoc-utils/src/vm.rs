@@ -0,0 +1,402 @@
+pub mod symbolic;
+pub mod three_bit;
+
+use std::{
+    collections::BTreeSet,
+    fmt::{Debug, Display},
+};
+
+// Where execution goes after an instruction.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Flow {
+    Next,
+    Goto(usize),
+    Offset(i64),
+    Halt,
+}
+
+// An instruction set: how each instruction changes the registers, what it
+// outputs and where execution continues.
+pub trait InstructionSet {
+    type Instr: Copy + Display;
+    type Registers: Clone + Debug;
+    type Output: Clone;
+
+    fn execute(
+        instr: Self::Instr,
+        regs: &mut Self::Registers,
+        output: &mut Vec<Self::Output>,
+    ) -> Flow;
+
+    // Clock cycles taken by an instruction
+    fn cycles(_instr: Self::Instr) -> usize {
+        1
+    }
+}
+
+// Why a run stopped.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Stop {
+    Halted,
+    Breakpoint(usize),
+    Limit,
+    Condition,
+}
+
+// The state just before an instruction was executed.
+#[derive(Debug, Clone)]
+pub struct TraceEntry<I: InstructionSet> {
+    pub step: usize,
+    pub ip: usize,
+    pub instr: I::Instr,
+    pub registers: I::Registers,
+}
+
+impl<I: InstructionSet> Display for TraceEntry<I> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:03}: {} {:?}", self.ip, self.instr, self.registers)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Machine<I: InstructionSet> {
+    program: Vec<I::Instr>,
+    registers: I::Registers,
+    ip: usize,
+    halted: bool,
+    steps: usize,
+    cycles: usize,
+    output: Vec<I::Output>,
+    breakpoints: BTreeSet<usize>,
+    limit: Option<usize>,
+    trace: Option<Vec<TraceEntry<I>>>,
+}
+
+impl<I: InstructionSet> Machine<I> {
+    pub fn new(program: Vec<I::Instr>, registers: I::Registers) -> Machine<I> {
+        Machine {
+            program,
+            registers,
+            ip: 0,
+            halted: false,
+            steps: 0,
+            cycles: 0,
+            output: Vec::new(),
+            breakpoints: BTreeSet::new(),
+            limit: None,
+            trace: None,
+        }
+    }
+
+    // Start again from the top with new registers, keeping the program,
+    // breakpoints, limit and tracing setting.
+    pub fn reset(&mut self, registers: I::Registers) {
+        self.registers = registers;
+        self.ip = 0;
+        self.halted = false;
+        self.steps = 0;
+        self.cycles = 0;
+        self.output.clear();
+        if let Some(trace) = self.trace.as_mut() {
+            trace.clear();
+        }
+    }
+
+    pub fn program(&self) -> &[I::Instr] {
+        &self.program
+    }
+
+    pub fn program_mut(&mut self) -> &mut Vec<I::Instr> {
+        &mut self.program
+    }
+
+    pub fn registers(&self) -> &I::Registers {
+        &self.registers
+    }
+
+    pub fn registers_mut(&mut self) -> &mut I::Registers {
+        &mut self.registers
+    }
+
+    pub fn ip(&self) -> usize {
+        self.ip
+    }
+
+    pub fn set_ip(&mut self, ip: usize) {
+        self.ip = ip;
+        self.halted = false;
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.halted || self.ip >= self.program.len()
+    }
+
+    // Instructions executed
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    pub fn cycles(&self) -> usize {
+        self.cycles
+    }
+
+    pub fn output(&self) -> &[I::Output] {
+        &self.output
+    }
+
+    pub fn take_output(&mut self) -> Vec<I::Output> {
+        std::mem::take(&mut self.output)
+    }
+
+    pub fn add_breakpoint(&mut self, ip: usize) {
+        self.breakpoints.insert(ip);
+    }
+
+    pub fn remove_breakpoint(&mut self, ip: usize) {
+        self.breakpoints.remove(&ip);
+    }
+
+    // Stop runs once this many instructions have been executed in total.
+    pub fn set_limit(&mut self, limit: Option<usize>) {
+        self.limit = limit;
+    }
+
+    pub fn enable_trace(&mut self) {
+        self.trace.get_or_insert_with(Vec::new);
+    }
+
+    pub fn trace(&self) -> &[TraceEntry<I>] {
+        self.trace.as_deref().unwrap_or_default()
+    }
+
+    // Execute one instruction, returning false if already halted.
+    pub fn step(&mut self) -> bool {
+        if self.is_halted() {
+            return false;
+        }
+        let instr = self.program[self.ip];
+        if let Some(trace) = self.trace.as_mut() {
+            trace.push(TraceEntry {
+                step: self.steps,
+                ip: self.ip,
+                instr,
+                registers: self.registers.clone(),
+            });
+        }
+        let flow = I::execute(instr, &mut self.registers, &mut self.output);
+        self.steps += 1;
+        self.cycles += I::cycles(instr);
+        match flow {
+            Flow::Next => self.ip += 1,
+            Flow::Goto(ip) => self.ip = ip,
+            Flow::Offset(offset) => match self.ip.checked_add_signed(offset as isize) {
+                Some(ip) => self.ip = ip,
+                None => self.halted = true,
+            },
+            Flow::Halt => self.halted = true,
+        }
+        true
+    }
+
+    // Run until halted, a breakpoint, the limit, or the condition holds
+    // before an instruction. The first instruction always runs, so a run can
+    // continue from a breakpoint.
+    pub fn run_until(&mut self, mut stop: impl FnMut(&Self) -> bool) -> Stop {
+        let mut first = true;
+        loop {
+            if self.is_halted() {
+                return Stop::Halted;
+            }
+            if self.limit.is_some_and(|limit| self.steps >= limit) {
+                return Stop::Limit;
+            }
+            if !first {
+                if self.breakpoints.contains(&self.ip) {
+                    return Stop::Breakpoint(self.ip);
+                }
+                if stop(self) {
+                    return Stop::Condition;
+                }
+            }
+            first = false;
+            self.step();
+        }
+    }
+
+    pub fn run(&mut self) -> Stop {
+        self.run_until(|_| false)
+    }
+
+    // One line per instruction, with its address
+    pub fn disassemble(&self) -> String {
+        self.program
+            .iter()
+            .enumerate()
+            .map(|(ip, instr)| format!("{ip:03}: {instr}\n"))
+            .collect()
+    }
+}
+
+// Build a number of `len` digits in `base`, most significant first, keeping a
+// prefix only while `accept(prefix, digits)` holds and backtracking from dead
+// ends. Digits are tried in increasing order, so the smallest match is found.
+pub fn search_digits(
+    base: u64,
+    len: usize,
+    mut accept: impl FnMut(u64, usize) -> bool,
+) -> Option<u64> {
+    fn search(
+        prefix: u64,
+        digits: usize,
+        base: u64,
+        len: usize,
+        accept: &mut dyn FnMut(u64, usize) -> bool,
+    ) -> Option<u64> {
+        if digits == len {
+            return Some(prefix);
+        }
+        (0..base).find_map(|d| {
+            let next = prefix.checked_mul(base)?.checked_add(d)?;
+            if accept(next, digits + 1) {
+                search(next, digits + 1, base, len, accept)
+            } else {
+                None
+            }
+        })
+    }
+    search(0, 0, base, len, &mut accept)
+}
+
+// The smallest input that makes a program output `target`, for programs that
+// consume their input a digit at a time from the least significant end and
+// output one value per digit. The first k digits of the input must produce
+// the last k values of the target. Each candidate runs for at most max_steps,
+// and one that doesn't halt by then is rejected.
+pub fn find_quine<I: InstructionSet>(
+    machine: &mut Machine<I>,
+    registers: &I::Registers,
+    set_input: impl Fn(&mut I::Registers, u64),
+    target: &[I::Output],
+    base: u64,
+    max_steps: usize,
+) -> Option<u64>
+where
+    I::Output: PartialEq,
+{
+    search_digits(base, target.len(), |input, digits| {
+        let mut regs = registers.clone();
+        set_input(&mut regs, input);
+        machine.reset(regs);
+        machine.set_limit(Some(max_steps));
+        machine.run() == Stop::Halted && machine.output() == &target[target.len() - digits..]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A cut down assembunny
+    #[derive(Debug, Copy, Clone)]
+    enum Bunny {
+        Cpy(i64, usize),
+        Inc(usize),
+        Dec(usize),
+        Jnz(usize, i64),
+        Out(usize),
+    }
+
+    impl Display for Bunny {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                Bunny::Cpy(v, r) => write!(f, "cpy {v} r{r}"),
+                Bunny::Inc(r) => write!(f, "inc r{r}"),
+                Bunny::Dec(r) => write!(f, "dec r{r}"),
+                Bunny::Jnz(r, o) => write!(f, "jnz r{r} {o}"),
+                Bunny::Out(r) => write!(f, "out r{r}"),
+            }
+        }
+    }
+
+    struct Assembunny;
+
+    impl InstructionSet for Assembunny {
+        type Instr = Bunny;
+        type Registers = [i64; 2];
+        type Output = i64;
+
+        fn execute(instr: Bunny, regs: &mut [i64; 2], output: &mut Vec<i64>) -> Flow {
+            match instr {
+                Bunny::Cpy(v, r) => regs[r] = v,
+                Bunny::Inc(r) => regs[r] += 1,
+                Bunny::Dec(r) => regs[r] -= 1,
+                Bunny::Jnz(r, o) if regs[r] != 0 => return Flow::Offset(o),
+                Bunny::Jnz(..) => {}
+                Bunny::Out(r) => output.push(regs[r]),
+            }
+            Flow::Next
+        }
+    }
+
+    #[test]
+    fn test_machine() {
+        // a = 3; b = 0; while a != 0 { b += 2; a -= 1; out b }
+        let program = vec![
+            Bunny::Cpy(3, 0),
+            Bunny::Inc(1),
+            Bunny::Inc(1),
+            Bunny::Dec(0),
+            Bunny::Out(1),
+            Bunny::Jnz(0, -4),
+        ];
+        let mut m = Machine::<Assembunny>::new(program, [0, 0]);
+        assert_eq!(m.run(), Stop::Halted);
+        assert_eq!(m.registers(), &[0, 6]);
+        assert_eq!(m.output(), &[2, 4, 6]);
+        assert_eq!(m.steps(), 16);
+
+        m.reset([0, 0]);
+        m.add_breakpoint(4);
+        assert_eq!(m.run(), Stop::Breakpoint(4));
+        assert_eq!(m.registers(), &[2, 2]);
+        assert_eq!(m.run(), Stop::Breakpoint(4));
+        assert_eq!(m.take_output(), vec![2]);
+        m.remove_breakpoint(4);
+        assert_eq!(m.run_until(|m| m.registers()[1] == 6), Stop::Condition);
+        assert_eq!(m.ip(), 3);
+
+        m.reset([0, 0]);
+        m.program_mut()[5] = Bunny::Jnz(1, -4);
+        m.set_limit(Some(100));
+        assert_eq!(m.run(), Stop::Limit);
+        assert_eq!(m.steps(), 100);
+    }
+
+    #[test]
+    fn test_search_digits() {
+        // The smallest 4 digit number whose prefixes are all divisible by
+        // their length
+        let n = search_digits(10, 4, |v, k| v % k as u64 == 0 && v > 0);
+        assert_eq!(n, Some(1020));
+        assert_eq!(search_digits(2, 3, |v, k| k < 3 && v % 2 == 1), None);
+    }
+
+    #[test]
+    fn test_find_quine() {
+        // Outputs the input, then spins forever unless it was 0
+        let mut m = Machine::<Assembunny>::new(vec![Bunny::Out(0), Bunny::Jnz(0, 0)], [0, 0]);
+        let input = |regs: &mut [i64; 2], a| regs[0] = a as i64;
+        assert_eq!(find_quine(&mut m, &[0, 0], input, &[0], 10, 100), Some(0));
+        assert_eq!(find_quine(&mut m, &[0, 0], input, &[1], 10, 100), None);
+    }
+
+    #[test]
+    fn test_trace() {
+        let mut m = Machine::<Assembunny>::new(vec![Bunny::Cpy(2, 0), Bunny::Dec(0)], [0, 0]);
+        m.enable_trace();
+        m.run();
+        let trace = m.trace().iter().map(|t| t.to_string()).collect::<Vec<_>>();
+        assert_eq!(trace, vec!["000: cpy 2 r0 [0, 0]", "001: dec r0 [2, 0]"]);
+        assert_eq!(m.disassemble(), "000: cpy 2 r0\n001: dec r0\n");
+    }
+}
@@ -0,0 +1,456 @@
+// Symbolic execution over bits. Registers hold words of bit expressions over
+// the bits of an unknown input, built in a shared, hash-consed graph, so each
+// output can be inspected or evaluated for any input without rerunning.
+use std::collections::{BTreeSet, HashMap};
+
+use super::{Flow, InstructionSet};
+
+pub type Bit = usize;
+
+// Bits are least significant first.
+pub type Word = Vec<Bit>;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+enum Gate {
+    Const(bool),
+    Input(usize),
+    Not(Bit),
+    And(Bit, Bit),
+    Or(Bit, Bit),
+    Xor(Bit, Bit),
+    // select ? then : else
+    Mux(Bit, Bit, Bit),
+}
+
+#[derive(Debug, Clone)]
+pub struct Logic {
+    gates: Vec<Gate>,
+    index: HashMap<Gate, Bit>,
+}
+
+impl Default for Logic {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Logic {
+    pub const FALSE: Bit = 0;
+    pub const TRUE: Bit = 1;
+
+    pub fn new() -> Logic {
+        let mut logic = Logic {
+            gates: Vec::new(),
+            index: HashMap::new(),
+        };
+        logic.add(Gate::Const(false));
+        logic.add(Gate::Const(true));
+        logic
+    }
+
+    pub fn len(&self) -> usize {
+        self.gates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.gates.is_empty()
+    }
+
+    fn add(&mut self, gate: Gate) -> Bit {
+        if let Some(&bit) = self.index.get(&gate) {
+            return bit;
+        }
+        self.gates.push(gate);
+        self.index.insert(gate, self.gates.len() - 1);
+        self.gates.len() - 1
+    }
+
+    pub fn constant(&self, b: bool) -> Bit {
+        if b {
+            Logic::TRUE
+        } else {
+            Logic::FALSE
+        }
+    }
+
+    pub fn value(&self, bit: Bit) -> Option<bool> {
+        match self.gates[bit] {
+            Gate::Const(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    // Bit i of the input, up to 128 input bits.
+    pub fn input(&mut self, i: usize) -> Bit {
+        assert!(i < 128);
+        self.add(Gate::Input(i))
+    }
+
+    pub fn not(&mut self, a: Bit) -> Bit {
+        match self.gates[a] {
+            Gate::Const(b) => self.constant(!b),
+            Gate::Not(x) => x,
+            _ => self.add(Gate::Not(a)),
+        }
+    }
+
+    pub fn and(&mut self, a: Bit, b: Bit) -> Bit {
+        let (a, b) = (a.min(b), a.max(b));
+        match (self.value(a), self.value(b)) {
+            (Some(false), _) | (_, Some(false)) => Logic::FALSE,
+            (Some(true), _) => b,
+            (_, Some(true)) => a,
+            _ if a == b => a,
+            _ => self.add(Gate::And(a, b)),
+        }
+    }
+
+    pub fn or(&mut self, a: Bit, b: Bit) -> Bit {
+        let (a, b) = (a.min(b), a.max(b));
+        match (self.value(a), self.value(b)) {
+            (Some(true), _) | (_, Some(true)) => Logic::TRUE,
+            (Some(false), _) => b,
+            (_, Some(false)) => a,
+            _ if a == b => a,
+            _ => self.add(Gate::Or(a, b)),
+        }
+    }
+
+    pub fn xor(&mut self, a: Bit, b: Bit) -> Bit {
+        let (a, b) = (a.min(b), a.max(b));
+        match (self.value(a), self.value(b)) {
+            (Some(x), Some(y)) => self.constant(x ^ y),
+            (Some(false), _) => b,
+            (Some(true), _) => self.not(b),
+            _ if a == b => Logic::FALSE,
+            _ => self.add(Gate::Xor(a, b)),
+        }
+    }
+
+    pub fn mux(&mut self, select: Bit, then: Bit, otherwise: Bit) -> Bit {
+        match (self.value(select), self.value(then), self.value(otherwise)) {
+            (Some(s), _, _) => {
+                if s {
+                    then
+                } else {
+                    otherwise
+                }
+            }
+            _ if then == otherwise => then,
+            (_, Some(true), Some(false)) => select,
+            (_, Some(false), Some(true)) => self.not(select),
+            _ => self.add(Gate::Mux(select, then, otherwise)),
+        }
+    }
+
+    // The value of every bit for the given input. Gates only refer to earlier
+    // gates, so one pass in order is enough.
+    pub fn eval(&self, input: u128) -> Vec<bool> {
+        let mut values: Vec<bool> = Vec::with_capacity(self.gates.len());
+        for gate in &self.gates {
+            let v = match *gate {
+                Gate::Const(b) => b,
+                Gate::Input(i) => input >> i & 1 == 1,
+                Gate::Not(a) => !values[a],
+                Gate::And(a, b) => values[a] && values[b],
+                Gate::Or(a, b) => values[a] || values[b],
+                Gate::Xor(a, b) => values[a] ^ values[b],
+                Gate::Mux(s, t, e) => {
+                    if values[s] {
+                        values[t]
+                    } else {
+                        values[e]
+                    }
+                }
+            };
+            values.push(v);
+        }
+        values
+    }
+
+    // The input bits an expression depends on.
+    pub fn support(&self, bit: Bit) -> BTreeSet<usize> {
+        let mut inputs = BTreeSet::new();
+        let mut seen = vec![false; self.gates.len()];
+        let mut stack = vec![bit];
+        while let Some(b) = stack.pop() {
+            if std::mem::replace(&mut seen[b], true) {
+                continue;
+            }
+            match self.gates[b] {
+                Gate::Const(_) => {}
+                Gate::Input(i) => {
+                    inputs.insert(i);
+                }
+                Gate::Not(a) => stack.push(a),
+                Gate::And(a, b) | Gate::Or(a, b) | Gate::Xor(a, b) => stack.extend([a, b]),
+                Gate::Mux(s, t, e) => stack.extend([s, t, e]),
+            }
+        }
+        inputs
+    }
+
+    // The expression for a bit. Gates used more than once are bound to a
+    // name in lets before it, so shared subterms are written out once.
+    pub fn format(&self, bit: Bit) -> String {
+        let mut uses = HashMap::new();
+        let mut stack = vec![bit];
+        while let Some(b) = stack.pop() {
+            *uses.entry(b).or_insert(0) += 1;
+            if uses[&b] > 1 {
+                continue;
+            }
+            match self.gates[b] {
+                Gate::Const(_) | Gate::Input(_) => {}
+                Gate::Not(a) => stack.push(a),
+                Gate::And(a, b) | Gate::Or(a, b) | Gate::Xor(a, b) => stack.extend([a, b]),
+                Gate::Mux(s, t, e) => stack.extend([s, t, e]),
+            }
+        }
+        // Gates only refer to earlier gates, so formatting in order has every
+        // operand ready, taken once if only used once.
+        let mut order = uses.keys().copied().collect::<Vec<_>>();
+        order.sort_unstable();
+        let mut lets = String::new();
+        let mut formatted = HashMap::<Bit, String>::new();
+        for b in order {
+            let mut operand = |a: Bit| {
+                if uses[&a] > 1 {
+                    formatted[&a].clone()
+                } else {
+                    formatted.remove(&a).unwrap()
+                }
+            };
+            let expr = match self.gates[b] {
+                Gate::Const(c) => (c as u8).to_string(),
+                Gate::Input(i) => format!("x{i}"),
+                Gate::Not(a) => format!("!{}", operand(a)),
+                Gate::And(a, c) => format!("({} & {})", operand(a), operand(c)),
+                Gate::Or(a, c) => format!("({} | {})", operand(a), operand(c)),
+                Gate::Xor(a, c) => format!("({} ^ {})", operand(a), operand(c)),
+                Gate::Mux(s, t, e) => format!("({} ? {} : {})", operand(s), operand(t), operand(e)),
+            };
+            let leaf = matches!(self.gates[b], Gate::Const(_) | Gate::Input(_));
+            if uses[&b] > 1 && !leaf {
+                lets.push_str(&format!("let t{b} = {expr};\n"));
+                formatted.insert(b, format!("t{b}"));
+            } else {
+                formatted.insert(b, expr);
+            }
+        }
+        lets + &formatted[&bit]
+    }
+
+    pub fn word_constant(&self, v: u64, width: usize) -> Word {
+        (0..width)
+            .map(|i| self.constant(i < 64 && v >> i & 1 == 1))
+            .collect()
+    }
+
+    // Input bits offset..offset + bits, zero extended to width.
+    pub fn word_input(&mut self, offset: usize, bits: usize, width: usize) -> Word {
+        (0..width)
+            .map(|i| {
+                if i < bits {
+                    self.input(offset + i)
+                } else {
+                    Logic::FALSE
+                }
+            })
+            .collect()
+    }
+
+    pub fn word_value(&self, word: &[Bit]) -> Option<u64> {
+        word.iter()
+            .rev()
+            .try_fold(0, |v, &b| Some(v << 1 | self.value(b)? as u64))
+    }
+
+    pub fn word_xor(&mut self, a: &[Bit], b: &[Bit]) -> Word {
+        a.iter().zip(b).map(|(&x, &y)| self.xor(x, y)).collect()
+    }
+
+    // The low bits of a word, zero extended back to its width.
+    pub fn word_truncate(&self, word: &[Bit], bits: usize) -> Word {
+        (0..word.len())
+            .map(|i| if i < bits { word[i] } else { Logic::FALSE })
+            .collect()
+    }
+
+    pub fn word_nonzero(&mut self, word: &[Bit]) -> Bit {
+        word.iter().fold(Logic::FALSE, |acc, &b| self.or(acc, b))
+    }
+
+    // Logical shift right by a symbolic amount, as a barrel shifter.
+    pub fn word_shr(&mut self, word: &[Bit], amount: &[Bit]) -> Word {
+        let width = word.len();
+        let mut result = word.to_vec();
+        let mut too_far = Logic::FALSE;
+        for (k, &s) in amount.iter().enumerate() {
+            let shift = 1usize.checked_shl(k as u32).filter(|&n| n < width);
+            let Some(shift) = shift else {
+                too_far = self.or(too_far, s);
+                continue;
+            };
+            result = (0..width)
+                .map(|i| {
+                    let shifted = result.get(i + shift).copied().unwrap_or(Logic::FALSE);
+                    self.mux(s, shifted, result[i])
+                })
+                .collect();
+        }
+        let zero = self.not(too_far);
+        result.into_iter().map(|b| self.and(zero, b)).collect()
+    }
+
+    pub fn eval_word(values: &[bool], word: &[Bit]) -> u64 {
+        word.iter().rev().fold(0, |v, &b| v << 1 | values[b] as u64)
+    }
+}
+
+// Where execution goes when a conditional instruction's condition might hold
+// or not.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SymFlow {
+    Always(Flow),
+    // Follow the flow if the condition holds, otherwise go on to the next
+    // instruction.
+    Branch(Bit, Flow),
+}
+
+// An instruction set that can also run on symbolic registers.
+pub trait SymbolicSet: InstructionSet {
+    type SymRegisters: Clone;
+
+    fn execute_symbolic(
+        instr: Self::Instr,
+        regs: &mut Self::SymRegisters,
+        logic: &mut Logic,
+        output: &mut Vec<Word>,
+    ) -> SymFlow;
+}
+
+#[derive(Debug, Clone)]
+pub struct SymbolicRun<R> {
+    pub registers: R,
+    pub outputs: Vec<Word>,
+    // Conditions that must hold on the input for execution to follow the
+    // path taken
+    pub assumptions: Vec<Bit>,
+    pub halted: bool,
+}
+
+// Run until the program halts or max_steps instructions. When a branch
+// depends on the input, `choose` picks whether to assume its condition holds.
+pub fn run_symbolic<S: SymbolicSet>(
+    program: &[S::Instr],
+    registers: S::SymRegisters,
+    logic: &mut Logic,
+    max_steps: usize,
+    mut choose: impl FnMut(&Logic, Bit) -> bool,
+) -> SymbolicRun<S::SymRegisters> {
+    let mut regs = registers;
+    let mut outputs = Vec::new();
+    let mut assumptions = Vec::new();
+    let mut ip = 0usize;
+    for _ in 0..max_steps {
+        if ip >= program.len() {
+            break;
+        }
+        let flow = match S::execute_symbolic(program[ip], &mut regs, logic, &mut outputs) {
+            SymFlow::Always(flow) => flow,
+            SymFlow::Branch(condition, flow) => {
+                let taken = match logic.value(condition) {
+                    Some(taken) => taken,
+                    None => {
+                        let taken = choose(logic, condition);
+                        let assumption = if taken {
+                            condition
+                        } else {
+                            logic.not(condition)
+                        };
+                        assumptions.push(assumption);
+                        taken
+                    }
+                };
+                if taken {
+                    flow
+                } else {
+                    Flow::Next
+                }
+            }
+        };
+        match flow {
+            Flow::Next => ip += 1,
+            Flow::Goto(target) => ip = target,
+            Flow::Offset(offset) => match ip.checked_add_signed(offset as isize) {
+                Some(target) => ip = target,
+                None => ip = usize::MAX,
+            },
+            Flow::Halt => ip = usize::MAX,
+        }
+    }
+    SymbolicRun {
+        registers: regs,
+        outputs,
+        assumptions,
+        halted: ip >= program.len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_logic() {
+        let mut logic = Logic::new();
+        let x = logic.word_input(0, 8, 16);
+        let amount = logic.word_input(8, 3, 16);
+        let shifted = logic.word_shr(&x, &amount);
+        let k = logic.word_constant(0b101, 16);
+        let mixed = logic.word_xor(&shifted, &k);
+        let low = logic.word_truncate(&mixed, 3);
+        for input in [0u128, 0x3ff, 0x5a7, 0x2c3] {
+            let values = logic.eval(input);
+            let (x, s) = ((input & 0xff) as u64, (input >> 8 & 7) as u64);
+            assert_eq!(Logic::eval_word(&values, &low), ((x >> s) ^ 5) & 7);
+        }
+        assert_eq!(logic.support(low[0]), BTreeSet::from_iter(0..11));
+        assert_eq!(logic.word_value(&k), Some(5));
+        assert_eq!(logic.word_value(&low), None);
+
+        let a = logic.input(0);
+        let b = logic.input(1);
+        let ab = logic.xor(a, b);
+        let ba = logic.xor(b, a);
+        assert_eq!(ab, ba);
+        assert_eq!(logic.xor(ab, ba), Logic::FALSE);
+        let not_a = logic.not(a);
+        assert_eq!(logic.not(not_a), a);
+        assert_eq!(logic.mux(a, Logic::TRUE, Logic::FALSE), a);
+        assert_eq!(logic.format(ab), "(x0 ^ x1)");
+        let both = logic.and(ab, not_a);
+        let either = logic.or(both, ab);
+        assert_eq!(
+            logic.format(either),
+            format!("let t{ab} = (x0 ^ x1);\n(t{ab} | (t{ab} & !x0))")
+        );
+    }
+
+    #[test]
+    fn test_format_shifted() {
+        // Each stage of the shifter muxes two bits of the one before, so
+        // across the word every intermediate bit is used twice
+        let mut logic = Logic::new();
+        let x = logic.word_input(0, 64, 64);
+        let amount = logic.word_input(64, 6, 64);
+        let shifted = logic.word_shr(&x, &amount);
+        let nonzero = logic.word_nonzero(&shifted);
+        let formatted = logic.format(nonzero);
+        assert!(formatted.len() < 64 * logic.len());
+        assert!(formatted.lines().count() > 1);
+        // Only the last line, the or of every bit, is long
+        assert!(formatted.lines().rev().skip(1).all(|l| l.len() < 100));
+    }
+}
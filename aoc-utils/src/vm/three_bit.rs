@@ -0,0 +1,319 @@
+// The 3-bit computer: three registers and eight instructions, each an opcode
+// followed by an operand in memory.
+use std::fmt::Display;
+
+use super::{
+    find_quine,
+    symbolic::{run_symbolic, Bit, Logic, SymFlow, SymbolicRun, SymbolicSet, Word},
+    Flow, InstructionSet, Machine,
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OpCode {
+    Adv(u8),
+    Bxl(u8),
+    Bst(u8),
+    Jnz(u8),
+    Bxc(u8),
+    Out(u8),
+    Bdv(u8),
+    Cdv(u8),
+}
+
+impl OpCode {
+    // None for an unknown opcode, or a combo operand that isn't 0 to 6.
+    pub fn from(a: u8, b: u8) -> Option<OpCode> {
+        let combo = b < 7;
+        let opcode = match a {
+            0 if combo => OpCode::Adv(b),
+            1 => OpCode::Bxl(b),
+            2 if combo => OpCode::Bst(b),
+            3 => OpCode::Jnz(b),
+            4 => OpCode::Bxc(b),
+            5 if combo => OpCode::Out(b),
+            6 if combo => OpCode::Bdv(b),
+            7 if combo => OpCode::Cdv(b),
+            _ => return None,
+        };
+        Some(opcode)
+    }
+}
+
+impl Display for OpCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OpCode::Adv(v) => write!(f, "ADV({v})"),
+            OpCode::Bxl(v) => write!(f, "BXL({v})"),
+            OpCode::Bst(v) => write!(f, "BST({v})"),
+            OpCode::Jnz(v) => write!(f, "JNZ({v})"),
+            OpCode::Bxc(v) => write!(f, "BXC({v})"),
+            OpCode::Out(v) => write!(f, "OUT({v})"),
+            OpCode::Bdv(v) => write!(f, "BDV({v})"),
+            OpCode::Cdv(v) => write!(f, "CDV({v})"),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct Registers {
+    pub a: u64,
+    pub b: u64,
+    pub c: u64,
+}
+
+impl Registers {
+    pub fn new(a: u64, b: u64, c: u64) -> Registers {
+        Registers { a, b, c }
+    }
+
+    fn combo(&self, v: u8) -> u64 {
+        match v {
+            0..=3 => v as u64,
+            4 => self.a,
+            5 => self.b,
+            6 => self.c,
+            _ => panic!("Invalid combo operand {v}"),
+        }
+    }
+}
+
+pub struct ThreeBit;
+
+impl InstructionSet for ThreeBit {
+    type Instr = OpCode;
+    type Registers = Registers;
+    type Output = u8;
+
+    fn execute(instr: OpCode, regs: &mut Registers, output: &mut Vec<u8>) -> Flow {
+        let shr = |regs: &Registers, v| regs.a.checked_shr(regs.combo(v) as u32).unwrap_or(0);
+        match instr {
+            OpCode::Adv(v) => regs.a = shr(regs, v),
+            OpCode::Bxl(v) => regs.b ^= v as u64,
+            OpCode::Bst(v) => regs.b = regs.combo(v) % 8,
+            // Jumps are to memory addresses, and each instruction takes two
+            OpCode::Jnz(v) if regs.a != 0 => return Flow::Goto(v as usize / 2),
+            OpCode::Jnz(_) => {}
+            OpCode::Bxc(_) => regs.b ^= regs.c,
+            OpCode::Out(v) => output.push((regs.combo(v) % 8) as u8),
+            OpCode::Bdv(v) => regs.b = shr(regs, v),
+            OpCode::Cdv(v) => regs.c = shr(regs, v),
+        }
+        Flow::Next
+    }
+}
+
+// Registers whose bits are expressions over the bits of an unknown input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymRegisters {
+    pub a: Word,
+    pub b: Word,
+    pub c: Word,
+}
+
+impl SymRegisters {
+    fn combo(&self, logic: &Logic, v: u8) -> Word {
+        match v {
+            0..=3 => logic.word_constant(v as u64, 64),
+            4 => self.a.clone(),
+            5 => self.b.clone(),
+            6 => self.c.clone(),
+            _ => panic!("Invalid combo operand {v}"),
+        }
+    }
+}
+
+impl SymbolicSet for ThreeBit {
+    type SymRegisters = SymRegisters;
+
+    fn execute_symbolic(
+        instr: OpCode,
+        regs: &mut SymRegisters,
+        logic: &mut Logic,
+        output: &mut Vec<Word>,
+    ) -> SymFlow {
+        let shr = |regs: &SymRegisters, logic: &mut Logic, v| {
+            let amount = regs.combo(logic, v);
+            logic.word_shr(&regs.a, &amount)
+        };
+        match instr {
+            OpCode::Adv(v) => regs.a = shr(regs, logic, v),
+            OpCode::Bxl(v) => {
+                let k = logic.word_constant(v as u64, 64);
+                regs.b = logic.word_xor(&regs.b, &k);
+            }
+            OpCode::Bst(v) => regs.b = logic.word_truncate(&regs.combo(logic, v), 3),
+            OpCode::Jnz(v) => {
+                let nonzero = logic.word_nonzero(&regs.a);
+                return SymFlow::Branch(nonzero, Flow::Goto(v as usize / 2));
+            }
+            OpCode::Bxc(_) => regs.b = logic.word_xor(&regs.b, &regs.c),
+            OpCode::Out(v) => output.push(regs.combo(logic, v)[..3].to_vec()),
+            OpCode::Bdv(v) => regs.b = shr(regs, logic, v),
+            OpCode::Cdv(v) => regs.c = shr(regs, logic, v),
+        }
+        SymFlow::Always(Flow::Next)
+    }
+}
+
+// Run with register A replaced by an unknown input of the given number of
+// bits, assuming every loop continues while A might still be non-zero. Each
+// output is three bits over the input bits.
+pub fn run_with_unknown_a(
+    memory: &[u8],
+    registers: Registers,
+    bits: usize,
+    logic: &mut Logic,
+) -> Option<SymbolicRun<SymRegisters>> {
+    let program = decode(memory)?;
+    let regs = SymRegisters {
+        a: logic.word_input(0, bits, 64),
+        b: logic.word_constant(registers.b, 64),
+        c: logic.word_constant(registers.c, 64),
+    };
+    let max_steps = 64 * program.len() * (bits + 1);
+    Some(run_symbolic::<ThreeBit>(
+        &program,
+        regs,
+        logic,
+        max_steps,
+        |_: &Logic, _: Bit| true,
+    ))
+}
+
+// The smallest value of A for which the program outputs itself.
+pub fn find_self_output(memory: &[u8], registers: Registers) -> Option<u64> {
+    let mut m = machine(memory, registers)?;
+    let max_steps = 64 * memory.len() * memory.len();
+    find_quine(
+        &mut m,
+        &registers,
+        |regs, a| regs.a = a,
+        memory,
+        8,
+        max_steps,
+    )
+}
+
+// Parse the puzzle input into its memory and initial registers.
+pub fn parse(input: &str) -> Option<(Vec<u8>, Registers)> {
+    let (registers, program) = input.split_once("\n\n")?;
+    let values = registers
+        .lines()
+        .map(|line| line.split(": ").last()?.trim().parse().ok())
+        .collect::<Option<Vec<u64>>>()?;
+    let [a, b, c] = values[..] else {
+        return None;
+    };
+    let memory = program
+        .trim()
+        .strip_prefix("Program: ")?
+        .split(',')
+        .map(|x| x.parse().ok())
+        .collect::<Option<Vec<u8>>>()?;
+    Some((memory, Registers::new(a, b, c)))
+}
+
+pub fn decode(memory: &[u8]) -> Option<Vec<OpCode>> {
+    memory
+        .chunks_exact(2)
+        .map(|pair| OpCode::from(pair[0], pair[1]))
+        .collect()
+}
+
+pub fn machine(memory: &[u8], registers: Registers) -> Option<Machine<ThreeBit>> {
+    Some(Machine::new(decode(memory)?, registers))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(memory: &[u8], registers: Registers) -> (Registers, Vec<u8>) {
+        let mut m = machine(memory, registers).unwrap();
+        m.run();
+        (*m.registers(), m.take_output())
+    }
+
+    #[test]
+    fn test_three_bit() {
+        let input = "Register A: 729\nRegister B: 0\nRegister C: 0\n\nProgram: 0,1,5,4,3,0\n";
+        let (memory, registers) = parse(input).unwrap();
+        assert_eq!(registers, Registers::new(729, 0, 0));
+        let (_, output) = run(&memory, registers);
+        assert_eq!(output, vec![4, 6, 3, 5, 6, 3, 5, 2, 1, 0]);
+
+        assert_eq!(run(&[2, 6], Registers::new(0, 0, 9)).0.b, 1);
+        let (_, output) = run(&[5, 0, 5, 1, 5, 4], Registers::new(10, 0, 0));
+        assert_eq!(output, vec![0, 1, 2]);
+        let (registers, output) = run(&[0, 1, 5, 4, 3, 0], Registers::new(2024, 0, 0));
+        assert_eq!(output, vec![4, 2, 5, 6, 7, 7, 7, 7, 3, 1, 0]);
+        assert_eq!(registers.a, 0);
+        let (registers, _) = run(&[1, 7], Registers::new(0, 29, 0));
+        assert_eq!(registers.b, 26);
+    }
+
+    #[test]
+    fn test_quine() {
+        let memory = [0, 3, 5, 4, 3, 0];
+        let registers = Registers::new(2024, 0, 0);
+        assert_eq!(find_self_output(&memory, registers), Some(117440));
+
+        // The program shifts A by three and outputs its low three bits, so
+        // each output depends on just three input bits.
+        let mut logic = Logic::new();
+        let run = run_with_unknown_a(&memory, registers, 18, &mut logic).unwrap();
+        assert!(run.halted);
+        assert_eq!(run.outputs.len(), 6);
+        let support = run
+            .outputs
+            .iter()
+            .map(|word| {
+                word.iter()
+                    .flat_map(|&b| logic.support(b))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(support[0], vec![3, 4, 5]);
+        assert_eq!(support[5], Vec::<usize>::new());
+        let values = logic.eval(117440);
+        let output = run
+            .outputs
+            .iter()
+            .map(|word| Logic::eval_word(&values, word) as u8)
+            .collect::<Vec<_>>();
+        assert_eq!(output, memory);
+    }
+
+    #[test]
+    fn test_symbolic_hash() {
+        // A typical puzzle program, mixing A with shifted copies of itself
+        let memory = [2, 4, 1, 3, 7, 5, 0, 3, 1, 5, 4, 4, 5, 5, 3, 0];
+        let registers = Registers::default();
+        let a = find_self_output(&memory, registers).unwrap();
+        let (_, output) = run(&memory, Registers::new(a, 0, 0));
+        assert_eq!(output, memory);
+
+        let mut logic = Logic::new();
+        let bits = 3 * memory.len();
+        let run = run_with_unknown_a(&memory, registers, bits, &mut logic).unwrap();
+        assert_eq!(run.outputs.len(), memory.len());
+        assert_eq!(run.assumptions.len(), memory.len() - 1);
+        let values = logic.eval(a as u128);
+        assert!(run.assumptions.iter().all(|&b| values[b]));
+        for (word, &expected) in run.outputs.iter().zip(&memory) {
+            assert_eq!(Logic::eval_word(&values, word), expected as u64);
+        }
+        let support = logic.support(run.outputs[0][0]);
+        assert!(support.iter().all(|&i| i < 10));
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let m = machine(&[0, 3, 5, 4, 3, 0], Registers::new(2024, 0, 0)).unwrap();
+        assert_eq!(m.disassemble(), "000: ADV(3)\n001: OUT(4)\n002: JNZ(0)\n");
+        assert_eq!(OpCode::from(1, 7), Some(OpCode::Bxl(7)));
+        assert_eq!(OpCode::from(5, 7), None);
+        assert!(machine(&[0, 7, 3, 0], Registers::default()).is_none());
+        assert!(decode(&[8, 0]).is_none());
+    }
+}